@@ -0,0 +1,239 @@
+use std::cmp::min;
+
+use macroquad::prelude::*;
+use macroquad::audio::*;
+use crate::game::*;
+use crate::bullet::*;
+use crate::colors::*;
+use crate::achievements::GameEvent;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum AbilityKind {
+    Dash,
+    Shield,
+    TimeSlow,
+    Bomb,
+}
+
+impl AbilityKind {
+    pub fn name(&self) -> &str {
+        match self {
+            AbilityKind::Dash => "Dash",
+            AbilityKind::Shield => "Shield",
+            AbilityKind::TimeSlow => "Slow",
+            AbilityKind::Bomb => "Bomb",
+        }
+    }
+
+    pub fn base_cooldown(&self) -> f32 {
+        match self {
+            AbilityKind::Dash => 1.5,
+            AbilityKind::Shield => 8.0,
+            AbilityKind::TimeSlow => 12.0,
+            AbilityKind::Bomb => 20.0,
+        }
+    }
+}
+
+pub struct Ability {
+    pub kind: AbilityKind,
+    pub cooldown: f32,
+    pub cooldown_t: f32, // time until the next charge comes back
+    pub charges: i32,
+    pub max_charges: i32,
+    pub power: f32,      // upgrades make each use stronger, 1 is base
+}
+
+impl Ability {
+    pub fn new(kind: AbilityKind) -> Self {
+        Ability {
+            kind,
+            cooldown: kind.base_cooldown(),
+            cooldown_t: 0.0,
+            charges: 1,
+            max_charges: 1,
+            power: 1.0,
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        if self.charges >= self.max_charges {
+            return;
+        }
+
+        self.cooldown_t -= dt;
+        if self.cooldown_t <= 0.0 {
+            self.charges += 1;
+            self.cooldown_t = self.cooldown;
+        }
+    }
+
+    // use up a charge, false if there's nothing to use
+    pub fn consume(&mut self) -> bool {
+        if self.charges <= 0 {
+            return false;
+        }
+
+        if self.charges == self.max_charges {
+            self.cooldown_t = self.cooldown;
+        }
+        self.charges -= 1;
+        true
+    }
+}
+
+// how slow the world runs while time slow is active
+pub const TIME_SLOW_SCALE: f32 = 0.35;
+pub const BOMB_RADIUS: f32 = 300.0;
+pub const BOMB_EFFECT_TIME: f32 = 0.4;
+
+impl Game {
+    // frame time for everything but the player, so time slow can stretch it
+    pub fn world_dt(&self) -> f32 {
+        get_frame_time() * self.time_scale
+    }
+
+    pub fn ability_update(&mut self) {
+        let dt = get_frame_time();
+        self.player.ability.update(dt);
+
+        if self.player.invuln_t > 0.0 {
+            self.player.invuln_t -= dt;
+        }
+        if self.player.dash_t > 0.0 {
+            self.player.dash_t -= dt;
+        }
+        if self.bomb_t > 0.0 {
+            self.bomb_t -= dt;
+        }
+        if self.shake_t > 0.0 {
+            self.shake_t -= dt;
+        }
+
+        if self.time_slow_t > 0.0 {
+            self.time_slow_t -= dt;
+            self.time_scale = TIME_SLOW_SCALE;
+        } else {
+            self.time_scale = 1.0;
+        }
+
+        if !(is_key_pressed(KeyCode::LeftShift) || is_key_pressed(KeyCode::RightShift)) {
+            return;
+        }
+        if !self.player.ability.consume() {
+            return;
+        }
+
+        let power = self.player.ability.power;
+        match self.player.ability.kind {
+            AbilityKind::Dash => {
+                let mut dir = Vec2 { x: self.player.dx, y: self.player.dy }.normalize_or_zero();
+                if dir == Vec2::ZERO {
+                    dir = Vec2 { x: self.player.shoot_dx, y: self.player.shoot_dy }.normalize_or_zero();
+                }
+                self.player.dash_dir = dir;
+                self.player.dash_t = 0.18;
+                self.player.invuln_t = 0.3 * power;
+            }
+            AbilityKind::Shield => {
+                self.player.shield = true;
+            }
+            AbilityKind::TimeSlow => {
+                self.time_slow_t = 3.0 * power;
+            }
+            AbilityKind::Bomb => {
+                self.bomb_t = BOMB_EFFECT_TIME;
+                self.shake_t = 0.3;
+                self.bullets.retain(|b| b.kind == BulletType::Player);
+                self.lasers.retain(|l| l.kind == BulletType::Player);
+
+                let center = Vec2 { x: self.player.x + self.player.size / 2.0, y: self.player.y + self.player.size / 2.0 };
+                for e in self.enemies.iter_mut() {
+                    if e.center().distance(center) < BOMB_RADIUS * power {
+                        e.health -= 3.0 * power;
+                    }
+                }
+            }
+        }
+    }
+
+    // every source of damage to the player goes through here, source is who to blame in the bestiary
+    pub fn damage_player(&mut self, amount: i32, source: Option<usize>) {
+        if self.player.invuln_t > 0.0 {
+            return;
+        }
+
+        if self.player.shield {
+            self.player.shield = false;
+            self.player.invuln_t = 0.5;
+            play_sound(&self.assets.menu_switch, PlaySoundParams { looped: false, volume: self.effect_level as f32 / 10.0 });
+            return;
+        }
+
+        self.player.health -= amount;
+        self.last_hit_by = source;
+        self.achievement_event(GameEvent::PlayerHit);
+        play_sound(&self.assets.hit, PlaySoundParams { looped: false, volume: self.effect_level as f32 / 10.0 });
+    }
+
+    pub fn heal_player(&mut self, amount: i32) {
+        self.player.health = min(self.player.max_health, self.player.health + amount);
+    }
+
+    // pixel offset for the whole screen while something is shaking it
+    pub fn screen_shake(&self) -> Vec2 {
+        if self.shake_t <= 0.0 || self.reduced_motion {
+            return Vec2::ZERO;
+        }
+
+        let strength = 12.0 * self.shake_t / 0.3;
+        Vec2 { x: rand::gen_range(-strength, strength), y: rand::gen_range(-strength, strength) }
+    }
+
+    pub fn ability_draw(&self) {
+        let color = match self.color_state {
+            ColorState::Primary => self.palette.fg_primary,
+            ColorState::Secondary => self.palette.fg_secondary
+        };
+        let center = Vec2 { x: self.player.x + self.player.size / 2.0, y: self.player.y + self.player.size / 2.0 };
+
+        if self.player.shield {
+            draw_circle_lines(center.x, center.y, self.player.size * 0.8, 3.0, color);
+        }
+
+        if self.bomb_t > 0.0 {
+            let t = 1.0 - self.bomb_t / BOMB_EFFECT_TIME;
+            let mut ring = WHITE;
+            ring.a = 1.0 - t;
+            draw_circle_lines(center.x, center.y, BOMB_RADIUS * self.player.ability.power * t, 8.0, ring);
+        }
+
+        if self.time_slow_t > 0.0 {
+            let mut tint = color;
+            tint.a = 0.08;
+            draw_rectangle(0.0, 0.0, DESIGN_WIDTH, DESIGN_HEIGHT, tint);
+        }
+    }
+
+    // cooldown box next to the hp bar
+    pub fn ability_hud_draw(&self, x: f32, y: f32, size: f32, color: Color) {
+        let a = &self.player.ability;
+        let mut bg = color;
+        bg.a = 0.3;
+
+        draw_rectangle(x, y, size, size, bg);
+        if a.charges < a.max_charges {
+            // fills up from the bottom as it recharges
+            let t = 1.0 - a.cooldown_t / a.cooldown;
+            draw_rectangle(x, y + size * (1.0 - t), size, size * t, bg);
+        } else {
+            draw_rectangle(x, y, size, size, color);
+        }
+        draw_rectangle_lines(x, y, size, size, 3.0, color);
+
+        draw_text_centered(a.kind.name(), x + size / 2.0, y + size + 20.0, 6.0, &self.assets.font_monogram);
+        if a.max_charges > 1 {
+            draw_text_centered(&a.charges.to_string(), x + size / 2.0, y + size / 2.0 + 8.0, 8.0, &self.assets.font_monogram);
+        }
+    }
+}
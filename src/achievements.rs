@@ -0,0 +1,233 @@
+use std::cmp::{max, min};
+
+use macroquad::prelude::*;
+
+use crate::game::*;
+use crate::survival::*;
+
+// Things that happen during a run that achievements care about
+#[derive(Clone, Copy)]
+pub enum GameEvent {
+    EnemyKilled,
+    BulletAbsorbed,
+    PlayerHit,
+    WaveReached(i32),
+    WaveCleared(i32),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Reward {
+    Palette(usize),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Goal {
+    Kills,
+    NoHitWaves,
+    AbsorbStreak,
+    Wave { wave: i32, difficulty: Option<i32> }, // any difficulty when None
+    Win { character: usize },                    // clear the last scripted wave as this character
+}
+
+pub struct Achievement {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub description: &'static str,
+    pub goal: Goal,
+    pub target: i32,
+    pub reward: Option<Reward>,
+}
+
+pub const ACHIEVEMENTS: [Achievement; 16] = [
+    Achievement { id: "kills_100", name: "Exterminator", description: "Kill 100 enemies", goal: Goal::Kills, target: 100, reward: None },
+    Achievement { id: "kills_1000", name: "Pest control", description: "Kill 1000 enemies", goal: Goal::Kills, target: 1000, reward: None },
+    Achievement { id: "nohit_1", name: "Untouchable", description: "Clear a wave without getting hit", goal: Goal::NoHitWaves, target: 1, reward: None },
+    Achievement { id: "nohit_10", name: "Ghost", description: "Clear 10 waves without getting hit", goal: Goal::NoHitWaves, target: 10, reward: None },
+    Achievement { id: "absorb_10", name: "Sponge", description: "Absorb 10 bullets in a row without getting hit", goal: Goal::AbsorbStreak, target: 10, reward: None },
+    Achievement { id: "absorb_50", name: "Black hole", description: "Absorb 50 bullets in a row without getting hit", goal: Goal::AbsorbStreak, target: 50, reward: None },
+    Achievement { id: "wave_10", name: "Getting warm", description: "Reach wave 10", goal: Goal::Wave { wave: 10, difficulty: None }, target: 1, reward: Some(Reward::Palette(1)) },
+    Achievement { id: "wave_25", name: "Seasoned", description: "Reach wave 25", goal: Goal::Wave { wave: 25, difficulty: None }, target: 1, reward: Some(Reward::Palette(2)) },
+    Achievement { id: "wave_10_easy", name: "Easy does it", description: "Reach wave 10 on Easy", goal: Goal::Wave { wave: 10, difficulty: Some(0) }, target: 1, reward: None },
+    Achievement { id: "wave_10_normal", name: "Normal person", description: "Reach wave 10 on Normal", goal: Goal::Wave { wave: 10, difficulty: Some(1) }, target: 1, reward: None },
+    Achievement { id: "wave_10_hard", name: "Hardened", description: "Reach wave 10 on Hard", goal: Goal::Wave { wave: 10, difficulty: Some(2) }, target: 1, reward: None },
+    Achievement { id: "wave_20_hard", name: "Diamond", description: "Reach wave 20 on Hard", goal: Goal::Wave { wave: 20, difficulty: Some(2) }, target: 1, reward: None },
+    Achievement { id: "win_garry", name: "Garry's day", description: "Clear the last scripted wave as Garry", goal: Goal::Win { character: 0 }, target: 1, reward: None },
+    Achievement { id: "win_bob", name: "BobBobBob!", description: "Clear the last scripted wave as Bob", goal: Goal::Win { character: 1 }, target: 1, reward: None },
+    Achievement { id: "win_john", name: "John did it", description: "Clear the last scripted wave as John", goal: Goal::Win { character: 2 }, target: 1, reward: None },
+    Achievement { id: "win_mark", name: "Mark my words", description: "Clear the last scripted wave as Mark", goal: Goal::Win { character: 3 }, target: 1, reward: None },
+];
+
+pub const TOAST_TIME: f32 = 3.0;
+
+// A short message in the corner, like an achievement popping
+pub struct Toast {
+    pub text: String,
+    pub t: f32,
+}
+
+// Saved as id:progress pairs so reordering the list doesn't scramble anything
+pub fn parse_achievements(s: &str) -> Vec<i32> {
+    let saved: Vec<(&str, i32)> = s.split(',')
+        .filter_map(|pair| {
+            let (id, value) = pair.split_once(':')?;
+            Some((id, value.parse().ok()?))
+        })
+        .collect();
+
+    ACHIEVEMENTS.iter()
+        .map(|a| saved.iter().find(|(id, _)| *id == a.id).map_or(0, |(_, v)| *v))
+        .collect()
+}
+
+pub fn achievements_to_string(progress: &[i32]) -> String {
+    ACHIEVEMENTS.iter().zip(progress)
+        .map(|(a, p)| format!("{}:{}", a.id, p))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+impl Game {
+    pub fn achievement_done(&self, idx: usize) -> bool {
+        self.achievements[idx] >= ACHIEVEMENTS[idx].target
+    }
+
+    // only modes with the normal waves count for wave and win goals
+    fn wave_goals_count(&self) -> bool {
+        self.mode != GameMode::Survival
+    }
+
+    pub fn achievement_event(&mut self, ev: GameEvent) {
+        let mut clean_wave = false;
+        match ev {
+            GameEvent::BulletAbsorbed => self.absorb_streak += 1,
+            GameEvent::PlayerHit => {
+                self.absorb_streak = 0;
+                self.wave_hit = true;
+            }
+            GameEvent::WaveCleared(_) => {
+                clean_wave = !self.wave_hit;
+                self.wave_hit = false;
+            }
+            GameEvent::WaveReached(w) if self.wave_goals_count() && w > self.best_wave => {
+                self.best_wave = w;
+                self.should_save = true;
+            }
+            _ => (),
+        }
+
+        for (i, a) in ACHIEVEMENTS.iter().enumerate() {
+            if self.achievement_done(i) {
+                continue;
+            }
+
+            let progress = match (a.goal, ev) {
+                (Goal::Kills, GameEvent::EnemyKilled) => self.achievements[i] + 1,
+                (Goal::NoHitWaves, GameEvent::WaveCleared(_)) if clean_wave => self.achievements[i] + 1,
+                (Goal::AbsorbStreak, GameEvent::BulletAbsorbed) => max(self.achievements[i], self.absorb_streak),
+                (Goal::Wave { wave, difficulty }, GameEvent::WaveReached(w)) if self.wave_goals_count() => {
+                    let diff_ok = difficulty.is_none_or(|d| d == self.difficulty_select);
+                    if w >= wave && diff_ok { a.target } else { continue }
+                }
+                (Goal::Win { character }, GameEvent::WaveCleared(w)) if self.wave_goals_count() => {
+                    if w as usize == self.waves.len() && character == self.selected_char as usize { a.target } else { continue }
+                }
+                _ => continue,
+            };
+
+            self.achievements[i] = min(progress, a.target);
+            self.should_save = true;
+            if self.achievement_done(i) {
+                self.complete_achievement(i);
+            }
+        }
+    }
+
+    fn complete_achievement(&mut self, idx: usize) {
+        let a = &ACHIEVEMENTS[idx];
+        self.toasts.push(Toast { text: format!("Achievement: {}", a.name), t: TOAST_TIME });
+
+        if let Some(Reward::Palette(p)) = a.reward {
+            self.unlock_palette(p);
+        }
+    }
+
+    pub fn toasts_update(&mut self) {
+        for t in self.toasts.iter_mut() {
+            t.t -= get_frame_time();
+        }
+        self.toasts.retain(|t| t.t > 0.0);
+    }
+
+    pub fn toasts_draw(&self) {
+        for (i, t) in self.toasts.iter().enumerate() {
+            let y = 40.0 + i as f32 * 60.0;
+            let mut bg = BLACK;
+            bg.a = 0.7 * (t.t / 0.3).min(1.0);
+            draw_rectangle(DESIGN_WIDTH - 520.0, y, 500.0, 50.0, bg);
+            draw_text_centered(&t.text, DESIGN_WIDTH - 270.0, y + 35.0, 8.0, &self.assets.font_monogram);
+        }
+    }
+
+    pub fn achievements_update(&mut self) {
+        self.background_update();
+        let up = is_key_pressed(KeyCode::W) || is_key_pressed(KeyCode::Up);
+        let down = is_key_pressed(KeyCode::S) || is_key_pressed(KeyCode::Down);
+
+        if up {
+            self.collection_x = max(0, self.collection_x - 1);
+            self.menu_switch();
+        }
+
+        if down {
+            self.collection_x = min(ACHIEVEMENTS.len() as i32 - 1, self.collection_x + 1);
+            self.menu_switch();
+        }
+
+        if is_key_pressed(KeyCode::Escape) {
+            self.game_state = GameState::MainMenu;
+            self.collection_x = 0;
+            self.menu_switch();
+        }
+    }
+
+    pub fn achievements_draw(&mut self) {
+        clear_background(BLACK);
+        self.background_draw();
+        self.help_text();
+
+        let x_center = DESIGN_WIDTH / 2.0;
+        let done = (0..ACHIEVEMENTS.len()).filter(|i| self.achievement_done(*i)).count();
+        draw_text_centered(&format!("Achievements {}/{}", done, ACHIEVEMENTS.len()), x_center, 100.0, 20.0, &self.assets.font_monogram);
+
+        // keep the selected one in view
+        let visible = 7;
+        let first = (self.collection_x - visible / 2).clamp(0, max(0, ACHIEVEMENTS.len() as i32 - visible));
+
+        for row in 0..visible {
+            let i = (first + row) as usize;
+            if i >= ACHIEVEMENTS.len() {
+                break;
+            }
+
+            let a = &ACHIEVEMENTS[i];
+            let y = 180.0 + row as f32 * 90.0;
+            let color = if self.achievement_done(i) { self.palette.fg_primary } else { GRAY };
+            let selected = i as i32 == self.collection_x;
+
+            draw_rectangle_lines(x_center - 400.0, y, 800.0, 80.0, if selected { 6.0 } else { 2.0 }, color);
+            draw_text_ex(a.name, x_center - 380.0, y + 35.0, TextParams { font: Some(&self.assets.font_monogram), font_size: 10, color, ..Default::default() });
+            draw_text_ex(a.description, x_center - 380.0, y + 65.0, TextParams { font: Some(&self.assets.font_monogram), font_size: 6, color: GRAY, ..Default::default() });
+
+            // progress bar on the right
+            let t = self.achievements[i] as f32 / a.target as f32;
+            draw_rectangle(x_center + 150.0, y + 30.0, 230.0 * t, 20.0, color);
+            draw_rectangle_lines(x_center + 150.0, y + 30.0, 230.0, 20.0, 2.0, color);
+            if a.target > 1 {
+                draw_text_centered(&format!("{}/{}", self.achievements[i], a.target), x_center + 265.0, y + 75.0, 5.0, &self.assets.font_monogram);
+            }
+            if let Some(Reward::Palette(_)) = a.reward {
+                draw_text_centered("Palette", x_center + 265.0, y + 22.0, 5.0, &self.assets.font_monogram);
+            }
+        }
+    }
+}
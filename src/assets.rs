@@ -11,11 +11,18 @@ pub struct Assets {
     pub upgrade_frame5: Texture2D,
     pub maxhp: Texture2D,
     pub projectile: Texture2D,
-    pub size: Texture2D,
     pub slowdmg: Texture2D,
     pub speed: Texture2D,
+    pub bounce: Texture2D,
+    pub homing: Texture2D,
+    pub split: Texture2D,
     pub shooter: Texture2D,
     pub tower: Texture2D,
+    pub rush: Texture2D,
+    pub splitter: Texture2D,
+    pub teleporter: Texture2D,
+    pub shielded: Texture2D,
+    pub sniper: Texture2D,
     pub menu1: Texture2D,
     pub menu2: Texture2D,
     pub garry: Texture2D,
@@ -35,6 +42,9 @@ pub struct Assets {
     pub shoot: Sound,
     pub hit: Sound,
     pub dead: Sound,
+    pub patterns: &'static str,
+    pub waves: &'static str,
+    pub credits: &'static str,
 }
 
 
@@ -65,10 +75,17 @@ impl Assets {
             border: Texture2D::from_file_with_format( include_bytes!("..\\assets\\border.png"), None),
             mark: Texture2D::from_file_with_format( include_bytes!("..\\assets\\mark.png"), None),
             locked: Texture2D::from_file_with_format( include_bytes!("..\\assets\\locked.png"), None),
-            size: Texture2D::from_file_with_format( include_bytes!("..\\assets\\size.png"), None),
             slowdmg: Texture2D::from_file_with_format( include_bytes!("..\\assets\\slowdmg.png"), None),
             speed: Texture2D::from_file_with_format( include_bytes!("..\\assets\\speed.png"), None),
+            bounce: Texture2D::from_file_with_format( include_bytes!("..\\assets\\bounce.png"), None),
+            homing: Texture2D::from_file_with_format( include_bytes!("..\\assets\\homing.png"), None),
+            split: Texture2D::from_file_with_format( include_bytes!("..\\assets\\split.png"), None),
             tower: Texture2D::from_file_with_format( include_bytes!("..\\assets\\tower.png"), None),
+            rush: Texture2D::from_file_with_format( include_bytes!("..\\assets\\rush.png"), None),
+            splitter: Texture2D::from_file_with_format( include_bytes!("..\\assets\\splitter.png"), None),
+            teleporter: Texture2D::from_file_with_format( include_bytes!("..\\assets\\teleporter.png"), None),
+            shielded: Texture2D::from_file_with_format( include_bytes!("..\\assets\\shielded.png"), None),
+            sniper: Texture2D::from_file_with_format( include_bytes!("..\\assets\\sniper.png"), None),
             menu1: Texture2D::from_file_with_format( include_bytes!("..\\assets\\menu1.png"), None),
             menu2: Texture2D::from_file_with_format( include_bytes!("..\\assets\\menu2.png"), None),
             shooter: Texture2D::from_file_with_format( include_bytes!("..\\assets\\shooter.png"), None),
@@ -78,6 +95,9 @@ impl Assets {
             shoot: load_sound_from_bytes( include_bytes!("..\\assets\\shoot.wav") ).await.unwrap(),
             hit: load_sound_from_bytes( include_bytes!("..\\assets\\hit.wav") ).await.unwrap(),
             dead: load_sound_from_bytes( include_bytes!("..\\assets\\dead.wav") ).await.unwrap(),
+            patterns: include_str!("..\\assets\\patterns.txt"),
+            waves: include_str!("..\\assets\\waves.txt"),
+            credits: include_str!("..\\assets\\CREDITS.txt"),
         }
     }
 }
\ No newline at end of file
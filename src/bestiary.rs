@@ -0,0 +1,145 @@
+use macroquad::prelude::*;
+
+use crate::game::*;
+use crate::enemy::*;
+use crate::bullet::*;
+use crate::colors::*;
+use crate::script::*;
+
+// One page of the bestiary, lined up with archetype_names
+pub struct BestiaryEntry {
+    pub name: String,
+    pub enemy: Enemy,
+    pub kills: i32,
+    pub deaths: i32, // runs this archetype ended
+}
+
+// "swarm_primary" -> "Swarm primary"
+pub fn display_name(name: &str) -> String {
+    let name = name.replace('_', " ");
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) => c.to_uppercase().chain(chars).collect(),
+        None => name,
+    }
+}
+
+// Saved as name:kills:deaths so new archetypes or pattern enemies don't scramble anything
+pub fn parse_bestiary(s: &str, bestiary: &mut [BestiaryEntry]) {
+    for entry in s.split(',') {
+        let mut parts = entry.splitn(3, ':');
+        let (Some(name), Some(kills), Some(deaths)) = (parts.next(), parts.next(), parts.next()) else { continue };
+        if let Some(b) = bestiary.iter_mut().find(|b| b.name == name) {
+            b.kills = kills.parse().unwrap_or(0);
+            b.deaths = deaths.parse().unwrap_or(0);
+        }
+    }
+}
+
+pub fn bestiary_to_string(bestiary: &[BestiaryEntry]) -> String {
+    bestiary.iter()
+        .map(|b| format!("{}:{}:{}", b.name, b.kills, b.deaths))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+impl Game {
+    pub fn build_bestiary(&self) -> Vec<BestiaryEntry> {
+        archetype_names(&self.pattern_enemies).iter()
+            .filter_map(|name| Some(BestiaryEntry {
+                name: name.to_string(),
+                enemy: self.archetype(name)?,
+                kills: 0,
+                deaths: 0,
+            }))
+            .collect()
+    }
+
+    fn attack_description(&self, e: &Enemy) -> String {
+        match e.kind {
+            EnemyType::FollowEnemy => String::from("Chases you down and hurts on contact"),
+            EnemyType::FollowShootEnemy => String::from("Keeps its distance and shoots at you"),
+            EnemyType::StaticCircleAttack => String::from("Stands still and lets out a damaging ring"),
+            EnemyType::PatternShooter => match e.pattern.and_then(|p| self.patterns.get(p)) {
+                Some(p) => format!("Fires the {} pattern", display_name(&p.name).to_lowercase()),
+                None => String::from("Fires bullet patterns"),
+            },
+            EnemyType::Charger => String::from("Winds up, then dashes straight at you"),
+            EnemyType::Splitter => format!("Splits in two when killed, {} times over", e.splits),
+            EnemyType::Teleporter => String::from("Blinks around the arena and shoots after landing"),
+            EnemyType::Shielded => String::from("Blocks bullets from the front, hit it from behind"),
+            EnemyType::Sniper => String::from("Aims a long laser, get out of the line"),
+        }
+    }
+
+    fn color_description(&self, e: &Enemy) -> Vec<String> {
+        let color = match e.state {
+            ColorState::Primary => "primary",
+            ColorState::Secondary => "secondary",
+        };
+        let mut lines = vec![match e.flip {
+            FlipMode::Never => format!("Always {}", color),
+            FlipMode::Timer => format!("Flips color every {} seconds", e.flip_tmax),
+            FlipMode::OnSwitch => String::from("Flips color after you switch"),
+        }];
+
+        if e.bullet_color == BulletColor::OppositePlayer && e.kind != EnemyType::FollowEnemy {
+            lines.push(String::from("Shoots the color you aren't"));
+        }
+        if let Some(immune) = e.immune {
+            let immune = if immune == ColorState::Primary { "primary" } else { "secondary" };
+            lines.push(format!("Immune to {} bullets", immune));
+        }
+        lines
+    }
+
+    pub fn bestiary_entry_draw(&self, b: &BestiaryEntry, x: f32, y: f32, size: f32, discovered: bool) {
+        let tint = if !discovered {
+            DARKGRAY
+        } else if b.enemy.state == ColorState::Primary {
+            self.palette.fg_primary
+        } else {
+            self.palette.fg_secondary
+        };
+
+        match self.enemy_sprite(b.enemy.kind) {
+            Some(texture) => draw_texture_ex(texture, x, y, tint,
+                DrawTextureParams { dest_size: Some( Vec2 { x: size, y: size}), ..Default::default() }),
+            None => draw_rectangle(x, y, size, size, tint),
+        }
+        if discovered && self.colorblind {
+            draw_state_glyph(b.enemy.state, x + size / 2.0, y + size / 2.0, size * 0.4, BLACK);
+        }
+    }
+
+    pub fn bestiary_detail_draw(&self, idx: usize) {
+        let b = &self.bestiary[idx];
+        let padding = 200.0;
+        let width = 400.0;
+        let height = DESIGN_HEIGHT - padding * 2.0;
+        let x_start = DESIGN_WIDTH - width - 25.0;
+        let x_center = x_start + width / 2.0;
+
+        draw_rectangle_lines(x_start, padding, width, height, 4.0, WHITE);
+
+        // nothing to say about something you've never beaten or been beaten by
+        if b.kills == 0 && b.deaths == 0 {
+            draw_text_centered("???", x_center, padding + 50.0, 10.0, &self.assets.font_monogram);
+            draw_text_centered_c("Not met yet", x_center, padding + height / 2.0, 8.0, &self.assets.font_monogram, GRAY);
+            return;
+        }
+
+        draw_text_centered(&display_name(&b.name), x_center, padding + 50.0, 10.0, &self.assets.font_monogram);
+        self.bestiary_entry_draw(b, x_center - 40.0, padding + 80.0, 80.0, true);
+
+        let e = &b.enemy;
+        draw_text_centered(&format!("Health {}   Score {}", e.health, e.score), x_center, padding + 210.0, 7.0, &self.assets.font_monogram);
+        draw_text_centered_c(&self.attack_description(e), x_center, padding + 260.0, 5.0, &self.assets.font_monogram, GRAY);
+        for (i, line) in self.color_description(e).iter().enumerate() {
+            draw_text_centered_c(line, x_center, padding + 310.0 + i as f32 * 30.0, 5.0, &self.assets.font_monogram, GRAY);
+        }
+
+        draw_text_centered(&format!("Killed {}", b.kills), x_center, padding + height + 40.0, 7.0, &self.assets.font_monogram);
+        draw_text_centered(&format!("Killed you {}", b.deaths), x_center, padding + height + 80.0, 7.0, &self.assets.font_monogram);
+    }
+}
@@ -1,9 +1,10 @@
 use macroquad::prelude::*;
 use crate::game::*;
 use crate::colors::*;
+use crate::collision::Shape;
 
 
-#[derive(PartialEq, Eq)]
+#[derive(PartialEq, Eq, Clone, Copy)]
 pub enum BulletType {
     Player,
     Enemy
@@ -21,29 +22,164 @@ pub struct Bullet {
     pub speed: f32,
     pub state: ColorState,
     pub kind: BulletType,
-    pub hit: bool
+    pub hit: bool,
+    pub trail: Vec<Vec2>,
+    pub mods: BulletModifiers,
+    pub age: f32,
+    pub hit_ids: Vec<u32>,
+    pub source: Option<usize>, // bestiary entry of the enemy that fired it
+}
+
+// Extra behaviour a bullet can have, shared by player and enemy bullets
+#[derive(Clone, Copy)]
+pub struct BulletModifiers {
+    pub pierce: i32,        // extra targets it goes through
+    pub bounces: i32,       // wall bounces left
+    pub homing: f32,        // turn speed towards the target, degrees per second
+    pub split: i32,         // bullets spawned when it hits something
+    pub lifetime: f32,      // seconds before it fades, 0 is forever
+    pub acceleration: f32,  // speed gained per second, can be negative
+}
+
+impl Default for BulletModifiers {
+    fn default() -> Self {
+        Self {
+            pierce: 0,
+            bounces: 0,
+            homing: 0.0,
+            split: 0,
+            lifetime: 0.0,
+            acceleration: 0.0,
+        }
+    }
 }
 
+// Where an enemy bullet gets its color from
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum BulletColor {
+    Primary,
+    Secondary,
+    Shooter,        // same as the enemy that fired it
+    OppositePlayer, // whatever the player isn't right now
+}
+
+// bullet id for the player in hit_ids, enemies use their own id
+pub const PLAYER_HIT_ID: u32 = u32::MAX;
+
+// how many past positions a bullet keeps for its trail
+pub const TRAIL_LENGTH: usize = 6;
+
 
 impl Bullet {
-    pub fn new(damage: i32, x: f32, y: f32, dx: f32, dy: f32, size: f32, speed: f32, kind: BulletType) -> Bullet {
+    // 1 damage, size 6 and speed 350 unless changed with the with_ functions
+    pub fn new(x: f32, y: f32, dir: Vec2, kind: BulletType) -> Bullet {
         Bullet {
-            x,y,dx,dy,kind, damage, size, speed,
+            x, y, kind,
+            dx: dir.x,
+            dy: dir.y,
+            damage: 1,
+            size: 6.0,
+            speed: 350.0,
             last_x: x,
             last_y: y,
             hit: false,
             state: ColorState::Primary,
+            trail: Vec::with_capacity(TRAIL_LENGTH),
+            mods: BulletModifiers::default(),
+            age: 0.0,
+            hit_ids: Vec::new(),
+            source: None,
         }
     }
 
-    pub fn update(&mut self) {
+    pub fn with_damage(mut self, damage: i32) -> Bullet {
+        self.damage = damage;
+        self
+    }
+
+    pub fn with_size(mut self, size: f32) -> Bullet {
+        self.size = size;
+        self
+    }
+
+    pub fn with_speed(mut self, speed: f32) -> Bullet {
+        self.speed = speed;
+        self
+    }
+
+    pub fn with_mods(mut self, mods: BulletModifiers) -> Bullet {
+        self.mods = mods;
+        self
+    }
+
+    pub fn update(&mut self, dt: f32) {
         self.last_x = self.x;
         self.last_y = self.y;
-        let dt = get_frame_time();
+
+        if self.trail.len() >= TRAIL_LENGTH {
+            self.trail.remove(0);
+        }
+        self.trail.push(Vec2 { x: self.x, y: self.y });
+
+        self.age += dt;
+        if self.mods.lifetime > 0.0 && self.age >= self.mods.lifetime {
+            self.hit = true;
+        }
+
+        // slowing bullets stop slowing at a crawl instead of going backwards
+        if self.mods.acceleration < 0.0 {
+            self.speed = f32::max(50.0, self.speed + self.mods.acceleration * dt);
+        } else {
+            self.speed += self.mods.acceleration * dt;
+        }
 
         self.x += self.dx * self.speed * dt;
         self.y += self.dy * self.speed * dt;
     }
+
+    // returns true if the bullet should keep flying after hitting this target
+    pub fn register_hit(&mut self, id: u32) -> bool {
+        self.hit_ids.push(id);
+        if self.mods.pierce > 0 {
+            self.mods.pierce -= 1;
+            return true;
+        }
+        self.hit = true;
+        false
+    }
+
+    // bullets this one breaks into, spread evenly around its direction
+    pub fn split(&self) -> Vec<Bullet> {
+        let mut out = Vec::new();
+        let count = self.mods.split;
+        for i in 0..count {
+            let angle = 360.0 / count as f32 * i as f32 + 180.0 / count as f32;
+            let dir = rotate_vec(Vec2 { x: self.dx, y: self.dy }, angle);
+            let mut mods = self.mods;
+            mods.split = 0;
+            mods.lifetime = 0.4;
+
+            let mut b = Bullet::new(self.x, self.y, dir, self.kind)
+                .with_damage(self.damage)
+                .with_size(self.size * 0.7)
+                .with_speed(self.speed)
+                .with_mods(mods);
+            b.state = self.state;
+            b.hit_ids = self.hit_ids.clone();
+            b.source = self.source;
+            out.push(b);
+        }
+        out
+    }
+
+    // the whole path travelled this frame, so fast bullets can't skip over anything
+    pub fn get_swept_shape(&self) -> Shape {
+        Shape::Capsule {
+            a: Vec2 { x: self.last_x, y: self.last_y },
+            b: Vec2 { x: self.x, y: self.y },
+            r: self.size,
+        }
+    }
 }
 
 pub struct CircleAttack {
@@ -52,24 +188,127 @@ pub struct CircleAttack {
     pub hit: bool,
     pub radius: f32,
     pub color: ColorState,
+    pub source: Option<usize>,
 }
 
 impl Game {
+    pub fn resolve_bullet_color(&self, c: BulletColor, shooter: ColorState) -> ColorState {
+        match c {
+            BulletColor::Primary => ColorState::Primary,
+            BulletColor::Secondary => ColorState::Secondary,
+            BulletColor::Shooter => shooter,
+            BulletColor::OppositePlayer => self.color_state.next(),
+        }
+    }
+
     pub fn bullet_draw(&mut self, b: &Bullet) {
         if b.kind == BulletType::Player {
-            draw_circle(b.x, b.y, b.size, WHITE);
-        } else {
-            let color = match b.state {
-                ColorState::Primary => self.palette.fg_primary,
-                ColorState::Secondary => self.palette.fg_secondary,
+            // with polarity on the color of your shots matters, so show it
+            let color = if !self.rules.polarity {
+                WHITE
+            } else {
+                match b.state {
+                    ColorState::Primary => self.palette.fg_primary,
+                    ColorState::Secondary => self.palette.fg_secondary,
+                }
             };
-            draw_circle(b.x, b.y, b.size, color);
+            self.bullet_trail_draw(b, color);
+            if self.rules.polarity && self.colorblind && b.state == ColorState::Secondary {
+                draw_poly(b.x, b.y, 4, b.size * 1.3, 45.0, color);
+            } else {
+                draw_circle(b.x, b.y, b.size, color);
+            }
+            return;
+        }
+
+        let color = match b.state {
+            ColorState::Primary => self.palette.fg_primary,
+            ColorState::Secondary => self.palette.fg_secondary,
+        };
+        self.bullet_trail_draw(b, color);
+
+        // color blind mode makes secondary ones diamonds instead of circles
+        if self.colorblind && b.state == ColorState::Secondary {
+            draw_poly(b.x, b.y, 4, b.size * 1.5 + 1.0, 0.0, BLACK);
+            draw_poly_lines(b.x, b.y, 4, b.size * 1.5, 0.0, 3.0, color);
+            draw_circle(b.x, b.y, b.size * 0.35, WHITE);
+            return;
         }
+
+        // outline so they read on both backgrounds, secondary ones get a hollow core
+        match b.state {
+            ColorState::Primary => {
+                draw_circle(b.x, b.y, b.size, color);
+                draw_circle_lines(b.x, b.y, b.size + 1.0, 2.0, WHITE);
+            }
+            ColorState::Secondary => {
+                draw_circle(b.x, b.y, b.size + 1.0, BLACK);
+                draw_circle_lines(b.x, b.y, b.size, 3.0, color);
+                draw_circle(b.x, b.y, b.size * 0.35, WHITE);
+            }
+        }
+    }
+
+    pub fn bullet_trail_draw(&self, b: &Bullet, color: Color) {
+        // oldest point first, fade in towards the bullet
+        let mut points = b.trail.clone();
+        points.push(Vec2 { x: b.x, y: b.y });
+
+        let count = points.len() as f32;
+        for i in 1..points.len() {
+            let t = i as f32 / count;
+            let mut trail_color = color;
+            trail_color.a = 0.5 * t;
+
+            let from = points[i - 1];
+            let to = points[i];
+            draw_line(from.x, from.y, to.x, to.y, b.size * 2.0 * t, trail_color);
+        }
+    }
+
+    // turn towards the nearest enemy, or the player for enemy bullets
+    pub fn bullet_homing(&self, b: &mut Bullet) {
+        if b.mods.homing <= 0.0 {
+            return;
+        }
+
+        let pos = Vec2 { x: b.x, y: b.y };
+        let target = match b.kind {
+            BulletType::Player => self.enemies.iter()
+                .filter(|e| !b.hit_ids.contains(&e.id))
+                .map(|e| Vec2 { x: e.x + e.size / 2.0, y: e.y + e.size / 2.0 })
+                .min_by(|a, c| a.distance(pos).total_cmp(&c.distance(pos))),
+            BulletType::Enemy => Some(Vec2 {
+                x: self.player.x + self.player.size / 2.0,
+                y: self.player.y + self.player.size / 2.0,
+            }),
+        };
+
+        let Some(target) = target else { return };
+        let dir = Vec2 { x: b.dx, y: b.dy };
+        let wanted = (target - pos).normalize_or_zero();
+        let angle = dir.angle_between(wanted).to_degrees();
+        let max_turn = b.mods.homing * self.world_dt();
+
+        let new_dir = rotate_vec(dir, angle.clamp(-max_turn, max_turn)).normalize_or_zero();
+        b.dx = new_dir.x;
+        b.dy = new_dir.y;
     }
 
     pub fn bullet_collision(&mut self, b: &mut Bullet) {
         if b.x < 0.0 || b.y < 0.0 || b.x > DESIGN_WIDTH || b.y > DESIGN_HEIGHT {
-            b.hit = true;
+            if b.mods.bounces > 0 {
+                b.mods.bounces -= 1;
+                if b.x < 0.0 || b.x > DESIGN_WIDTH { b.dx = -b.dx; }
+                if b.y < 0.0 || b.y > DESIGN_HEIGHT { b.dy = -b.dy; }
+                b.x = b.x.clamp(0.0, DESIGN_WIDTH);
+                b.y = b.y.clamp(0.0, DESIGN_HEIGHT);
+                // the bounce isn't a real path, don't sweep through the wall
+                b.last_x = b.x;
+                b.last_y = b.y;
+            } else {
+                b.hit = true;
+            }
             return;
         }
 
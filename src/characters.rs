@@ -6,7 +6,9 @@ use macroquad::audio::*;
 
 use crate::assets::Assets;
 use crate::game::*;
+use crate::survival::*;
 use crate::player::Player;
+use crate::ability::*;
 
 #[derive(PartialEq, Eq)]
 pub enum CharacterKind {
@@ -23,7 +25,20 @@ pub struct Character {
     pub damage: i32,
     pub speed: f32,
     pub max_diff: i32,
-    pub kind: CharacterKind
+    pub kind: CharacterKind,
+    pub ability: AbilityKind,
+}
+
+pub fn difficulty_name(d: i32) -> &'static str {
+    match d {
+        0 => "Easy",
+        1 => "Normal",
+        2 => "Hard",
+        3 => "Hard+",
+        4 => "Hard++",
+        5 => "Hard+++",
+        _ => "lol"
+    }
 }
 
 impl Character {
@@ -58,9 +73,21 @@ impl Game {
             }
 
             if interact {
-                self.game_state = GameState::Playing;
-                stop_sound(&self.assets.menu_song);
-                play_sound(&self.assets.play_song, PlaySoundParams { looped: true, volume: self.music_level as f32 / 10.0});
+                self.menu_selected += 1;
+                self.menu_switch();
+            }
+        }
+
+        if self.menu_selected == 2 {
+            if left || right {
+                self.rules.polarity = !self.rules.polarity;
+                self.menu_switch();
+            }
+
+            if interact {
+                self.game_state = GameState::Mutators;
+                self.menu_selected = 0;
+                self.menu_switch();
             }
         }
 
@@ -82,7 +109,7 @@ impl Game {
 
             if is_key_pressed(KeyCode::Escape) {
                 self.game_state = GameState::MainMenu;
-                self.menu_selected = 0;
+                self.menu_selected = if self.mode == GameMode::Survival { 1 } else { 0 };
                 self.menu_switch();
             }
         } else {
@@ -94,6 +121,23 @@ impl Game {
 
     }
 
+    // a fresh player with the selected character's ability
+    pub fn new_player(&self) -> Player {
+        Player::new(self.characters[self.selected_char as usize].ability)
+    }
+
+    pub fn start_run(&mut self) {
+        // the daily seeds its own, anything else just needs to differ between runs
+        if self.mode != GameMode::Daily {
+            self.run_rng.seed(((rand::rand() as u64) << 32) | rand::rand() as u64);
+        }
+        self.player = self.new_player();
+        self.apply_mutators();
+        self.game_state = GameState::Playing;
+        stop_sound(&self.assets.menu_song);
+        play_sound(&self.assets.play_song, PlaySoundParams { looped: true, volume: self.music_level as f32 / 10.0});
+    }
+
     pub fn characters_draw(&mut self) {
         let x_center = DESIGN_WIDTH / 2.0;
         clear_background(BLACK);
@@ -104,27 +148,32 @@ impl Game {
         let texture = c.get_sprite(&self.assets);
 
         draw_text_centered(&c.name, x_center, 100.0, 30.0, &self.assets.font_monogram);
+        if self.mode == GameMode::Survival {
+            draw_text_centered("Survival", x_center, 160.0, 10.0, &self.assets.font_monogram);
+        }
         draw_texture(texture, x_center - 100.0, 200.0, WHITE);
-        let diff_text = match self.difficulty_select {
-            0 => "Easy",
-            1 => "Normal",
-            2 => "Hard",
-            3 => "Hard+",
-            4 => "Hard++",
-            5 => "Hard+++",
-            _ => "lol"
-        };
+        let diff_text = difficulty_name(self.difficulty_select);
         if self.menu_selected == 1 {
             draw_text_centered(&format!("> {} <",diff_text), x_center, 500.0, 15.0, &self.assets.font_monogram);
         } else {
             draw_text_centered(diff_text, x_center, 500.0, 15.0, &self.assets.font_monogram);
         }
 
+        if self.menu_selected == 2 {
+            draw_text_centered(&format!("> {} <", self.rules.name()), x_center, 780.0, 15.0, &self.assets.font_monogram);
+        } else {
+            draw_text_centered(self.rules.name(), x_center, 780.0, 15.0, &self.assets.font_monogram);
+        }
+        if self.rules.polarity {
+            draw_text_centered_c("Shoot the opposite color: x2 damage, same color: x0.5", x_center, 830.0, 7.0, &self.assets.font_monogram, GRAY);
+        }
+
 
         draw_text_ex("Stats", 150.0, 240.0, TextParams { font: Some(&self.assets.font_monogram), font_size: 15, ..Default::default()});
         draw_text_ex("Health: 100", 150.0, 300.0, TextParams { font: Some(&self.assets.font_monogram), font_size: 10, ..Default::default()});
         draw_text_ex("Damage: 1", 150.0, 340.0, TextParams { font: Some(&self.assets.font_monogram), font_size: 10, ..Default::default()});
         draw_text_ex("Speed: 10", 150.0, 380.0, TextParams { font: Some(&self.assets.font_monogram), font_size: 10, ..Default::default()});
+        draw_text_ex(&format!("Ability: {}", c.ability.name()), 150.0, 420.0, TextParams { font: Some(&self.assets.font_monogram), font_size: 10, ..Default::default()});
 
         for i in 0..self.characters.len() {
             let c = &self.characters[i];
@@ -21,7 +21,7 @@ impl Game {
                     x: x,
                     y: DESIGN_HEIGHT/2.0 - upg_size/2.0,
                     size: upg_size,
-                    kind: self.upg_list[rand::gen_range(0, self.upg_list.len())],
+                    kind: self.upg_list[self.run_rng.below(self.upg_list.len())],
                     should_exist: true,
                 }
             )
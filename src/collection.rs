@@ -17,13 +17,15 @@ impl Game {
 
         if is_key_pressed(KeyCode::Q) {
             self.menu_selected = max(0, self.menu_selected - 1);
+            self.collection_x = 0;
         }
         if is_key_pressed(KeyCode::E) {
             self.menu_selected = min(2, self.menu_selected + 1);
+            self.collection_x = 0;
         }
 
         if is_key_pressed(KeyCode::Escape) {
-            self.menu_selected = 3;
+            self.menu_selected = 5;
             self.game_state = GameState::MainMenu;
         }
 
@@ -37,13 +39,20 @@ impl Game {
 
         match self.menu_selected {
             0 => { // Items
+                if right {
+                    self.collection_x = min(self.collection_x + 1, self.upg_list.len() as i32 - 1)
+                }
+
+                if down && self.upg_list.len() as i32 > self.collection_x + 5 {
+                    self.collection_x += 5;
+                }
             },
             1 => { // Enemies
                 if right {
-                    self.collection_x = min(self.collection_x + 1, self.enemy_list.len() as i32 - 1)
+                    self.collection_x = min(self.collection_x + 1, self.bestiary.len() as i32 - 1)
                 }
 
-                if down && self.enemy_list.len() as i32 - 1 >= self.collection_x + 5 {
+                if down && self.bestiary.len() as i32 > self.collection_x + 5 {
                     self.collection_x += 5;
                 }
             }
@@ -78,18 +87,38 @@ impl Game {
         let offset = 160.0;
         match self.menu_selected {
             0 => { // Items
+                for i in 0..self.upg_list.len() {
+                    let upg = &self.upg_list[i];
+                    let x = start_pos.x + ((i as f32 % 5.0) * offset);
+                    let y = start_pos.y + ((i as f32 / 5.0).floor() * offset);
+                    if i as i32 == self.collection_x {
+                        draw_texture_ex(&self.assets.border, x - 10.0, y - 10.0, WHITE, 
+                            DrawTextureParams { dest_size: Some( Vec2 { x: 120.0, y: 120.0}), ..Default::default() })
+                    }
+                    // undiscovered ones only show their outline
+                    let tint = if self.upgrade_seen[i] { WHITE } else { DARKGRAY };
+                    draw_texture_ex(upg.kind.get_texture(&self.assets), x, y, tint,
+                        DrawTextureParams { dest_size: Some( Vec2 { x: 100.0, y: 100.0}), ..Default::default() });
+                }
 
+                if (self.collection_x as usize) < self.upg_list.len() {
+                    self.item_detail_draw(self.collection_x as usize);
+                }
             },
             1 => { // Enemies
-                for i in 0..self.enemy_list.len() {
-                    let e = self.enemy_list[i];
+                for i in 0..self.bestiary.len() {
+                    let b = &self.bestiary[i];
                     let x = start_pos.x + ((i as f32 % 5.0) * offset);
                     let y = start_pos.y + ((i as f32 / 5.0).floor() * offset);
                     if i as i32 == self.collection_x {
                         draw_texture_ex(&self.assets.border, x - 10.0, y - 10.0, WHITE, 
                             DrawTextureParams { dest_size: Some( Vec2 { x: 120.0, y: 120.0}), ..Default::default() })
                     }
-                    draw_rectangle(x, y, 100.0, 100.0, WHITE);
+                    self.bestiary_entry_draw(b, x, y, 100.0, b.kills > 0 || b.deaths > 0);
+                }
+
+                if (self.collection_x as usize) < self.bestiary.len() {
+                    self.bestiary_detail_draw(self.collection_x as usize);
                 }
             }
             2 => { // Bosses
@@ -100,4 +129,36 @@ impl Game {
             }
         }
     }
-}
\ No newline at end of file
+
+    pub fn item_detail_draw(&self, idx: usize) {
+        let upg = &self.upg_list[idx];
+        let padding = 200.0;
+        let width = 400.0;
+        let height = DESIGN_HEIGHT - padding * 2.0;
+        let x_start = DESIGN_WIDTH - width - 25.0;
+        let x_center = x_start + width / 2.0;
+
+        if !self.upgrade_seen[idx] {
+            draw_texture_ex(&self.assets.upgrade_frame, x_start, padding, DARKGRAY,
+                DrawTextureParams { dest_size: Some( Vec2 { x: width, y: height} ), ..Default::default() });
+            draw_text_centered("???", x_center, padding + 50.0, 10.0, &self.assets.font_monogram);
+            draw_text_centered_c("Not found yet", x_center, padding + height / 2.0, 8.0, &self.assets.font_monogram, GRAY);
+            return;
+        }
+
+        draw_texture_ex(upg.rarity.get_frame(&self.assets), x_start, padding, WHITE,
+            DrawTextureParams { dest_size: Some( Vec2 { x: width, y: height} ), ..Default::default() });
+
+        draw_text_centered(&upg.name, x_center, padding + 50.0, 10.0, &self.assets.font_monogram);
+        draw_text_centered_c(upg.rarity.name(), x_center, padding + 85.0, 6.0, &self.assets.font_monogram, GRAY);
+
+        let text: Vec<&str> = upg.description.split(';').collect();
+        let size = 20.0 * text.len() as f32;
+        for (i, line) in text.iter().enumerate() {
+            draw_text_centered(line, x_center, padding + height / 2.0 + (i as f32 * 40.0) - size, 10.0, &self.assets.font_monogram);
+        }
+
+        draw_text_centered_c(&upg.lore, x_center, padding + height - 70.0, 6.0, &self.assets.font_monogram, GRAY);
+        draw_text_centered(&format!("Picked {} times", self.upgrade_picks[idx]), x_center, padding + height + 40.0, 7.0, &self.assets.font_monogram);
+    }
+}
@@ -1,27 +1,143 @@
-use std::cmp::min;
-
 use macroquad::prelude::*;
-use macroquad::audio::*;
 use crate::enemy::*;
 use crate::game::*;
 use crate::bullet::*;
+use crate::rules::Mutator;
+use crate::achievements::GameEvent;
+
+// Collision shapes, players are circles, enemies are boxes, and a capsule is
+// a circle swept along a line segment
+#[derive(Clone, Copy)]
+pub enum Shape {
+    Circle { x: f32, y: f32, r: f32 },
+    Aabb(Rect),
+    Capsule { a: Vec2, b: Vec2, r: f32 },
+}
+
+impl Shape {
+    pub fn overlaps(&self, other: &Shape) -> bool {
+        match (*self, *other) {
+            (Shape::Aabb(r1), Shape::Aabb(r2)) => rect_collide(r1, r2),
+            (Shape::Circle { x, y, r }, other) | (other, Shape::Circle { x, y, r }) => {
+                other.distance_to_point(vec2(x, y)) < r
+            }
+            (Shape::Capsule { a, b, r }, Shape::Aabb(rect)) | (Shape::Aabb(rect), Shape::Capsule { a, b, r }) => {
+                segment_rect_distance(a, b, rect) < r
+            }
+            (Shape::Capsule { a, b, r }, Shape::Capsule { a: c, b: d, r: r2 }) => {
+                segment_segment_distance(a, b, c, d) < r + r2
+            }
+        }
+    }
+
+    // distance from a point to the edge of the shape, 0 when inside
+    pub fn distance_to_point(&self, p: Vec2) -> f32 {
+        match *self {
+            Shape::Circle { x, y, r } => (p.distance(vec2(x, y)) - r).max(0.0),
+            Shape::Aabb(rect) => {
+                let closest = vec2(p.x.clamp(rect.x, rect.x + rect.w), p.y.clamp(rect.y, rect.y + rect.h));
+                p.distance(closest)
+            }
+            Shape::Capsule { a, b, r } => (point_segment_distance(p, a, b) - r).max(0.0),
+        }
+    }
+
+    // furthest distance from a point to any part of the shape
+    pub fn max_distance_to_point(&self, p: Vec2) -> f32 {
+        match *self {
+            Shape::Circle { x, y, r } => p.distance(vec2(x, y)) + r,
+            Shape::Aabb(rect) => {
+                let dx = f32::max((p.x - rect.x).abs(), (p.x - rect.x - rect.w).abs());
+                let dy = f32::max((p.y - rect.y).abs(), (p.y - rect.y - rect.h).abs());
+                (dx * dx + dy * dy).sqrt()
+            }
+            Shape::Capsule { a, b, r } => f32::max(p.distance(a), p.distance(b)) + r,
+        }
+    }
+
+    // ring of given radius and thickness around (x, y), like a CircleAttack
+    pub fn overlaps_ring(&self, x: f32, y: f32, radius: f32, thickness: f32) -> bool {
+        let center = vec2(x, y);
+        let inner = radius - thickness / 2.0;
+        let outer = radius + thickness / 2.0;
+
+        self.distance_to_point(center) <= outer && self.max_distance_to_point(center) >= inner
+    }
+}
+
+pub fn point_segment_distance(p: Vec2, a: Vec2, b: Vec2) -> f32 {
+    let ab = b - a;
+    let len = ab.length_squared();
+    if len == 0.0 {
+        return p.distance(a);
+    }
+
+    let t = ((p - a).dot(ab) / len).clamp(0.0, 1.0);
+    p.distance(a + ab * t)
+}
+
+pub fn segments_intersect(a: Vec2, b: Vec2, c: Vec2, d: Vec2) -> bool {
+    let cross = |o: Vec2, p: Vec2, q: Vec2| (p - o).perp_dot(q - o);
+    let d1 = cross(c, d, a);
+    let d2 = cross(c, d, b);
+    let d3 = cross(a, b, c);
+    let d4 = cross(a, b, d);
+
+    ((d1 > 0.0 && d2 < 0.0) || (d1 < 0.0 && d2 > 0.0)) && ((d3 > 0.0 && d4 < 0.0) || (d3 < 0.0 && d4 > 0.0))
+}
+
+pub fn segment_segment_distance(a: Vec2, b: Vec2, c: Vec2, d: Vec2) -> f32 {
+    if segments_intersect(a, b, c, d) {
+        return 0.0;
+    }
+
+    f32::min(
+        f32::min(point_segment_distance(a, c, d), point_segment_distance(b, c, d)),
+        f32::min(point_segment_distance(c, a, b), point_segment_distance(d, a, b)),
+    )
+}
+
+pub fn segment_rect_distance(a: Vec2, b: Vec2, rect: Rect) -> f32 {
+    if rect.contains(a) || rect.contains(b) {
+        return 0.0;
+    }
+
+    let corners = [
+        vec2(rect.x, rect.y),
+        vec2(rect.x + rect.w, rect.y),
+        vec2(rect.x + rect.w, rect.y + rect.h),
+        vec2(rect.x, rect.y + rect.h),
+    ];
+
+    let mut dist = f32::MAX;
+    for i in 0..4 {
+        let c = corners[i];
+        let d = corners[(i + 1) % 4];
+        dist = dist.min(segment_segment_distance(a, b, c, d));
+    }
+    dist
+}
 
 impl Game {
     // player bullet collides with enemy
     pub fn bullet_enemy_coll(&mut self, b: &mut Bullet) {
         let mut enemies = std::mem::take(&mut self.enemies);
         for enemy in enemies.iter_mut() {
-            let hit = rect_collide(
-                Rect{
-                    x: b.x - b.size,
-                    y: b.y - b.size,
-                    w: b.size * 2.0,
-                    h: b.size * 2.0,
-                },enemy.get_rect()); 
-            
+            if b.hit || b.hit_ids.contains(&enemy.id) {
+                continue;
+            }
+
+            let hit = b.get_swept_shape().overlaps(&enemy.get_shape());
+
             if hit {
-                b.hit = hit;
-                enemy.health -= self.player.damage;
+                // the shield stops the bullet outright, no pierce used and nothing splits off
+                if enemy.blocks(Vec2 { x: b.last_x, y: b.last_y }) {
+                    b.hit = true;
+                    continue;
+                }
+                b.register_hit(enemy.id);
+                enemy.health -= self.player.damage * self.damage_multiplier(b.state, enemy);
+                self.bullets.append(&mut b.split());
             }
         }
         self.enemies = enemies;
@@ -29,46 +145,195 @@ impl Game {
 
     // enemy bullet collides with player
     pub fn bullet_player_coll(&mut self, b: &mut Bullet) {
-        let hit = rect_collide(
-            self.player.get_rect(),
-            Rect{
-                x: b.x - b.size,
-                y: b.y - b.size,
-                w: b.size * 2.0,
-                h: b.size * 2.0,
-            }
-        );
+        if b.hit_ids.contains(&PLAYER_HIT_ID) {
+            return;
+        }
+
+        let hit = self.player.get_shape().overlaps(&b.get_swept_shape());
 
         if hit {
-            b.hit = hit;
+            b.register_hit(PLAYER_HIT_ID);
+            self.bullets.append(&mut b.split());
             if b.state == self.color_state {
-                self.player.health = min(self.player.max_health, self.player.health + self.player.heal_from_b)
+                self.achievement_event(GameEvent::BulletAbsorbed);
+                if !self.rules.has(Mutator::NoHeal) {
+                    self.heal_player(self.player.heal_from_b);
+                }
             } else {
-                self.player.health -= b.damage;
-                play_sound(&self.assets.hit, PlaySoundParams { looped: false, volume: self.effect_level as f32 / 10.0 });
+                self.damage_player(b.damage, b.source);
             }
         }
     }
+
+    // circle attack ring collides with player
+    pub fn circle_player_coll(&mut self, c: &mut CircleAttack) {
+        if c.hit {
+            return;
+        }
+
+        // ring is drawn 5px thick
+        let hit = self.player.get_shape().overlaps_ring(c.x, c.y, c.radius, 5.0);
+        if hit && self.color_state != c.color {
+            c.hit = true;
+            self.damage_player(2, c.source);
+        }
+    }
     // =========== ENEMIES =============
 
     // enemy and player collision
     pub fn enemy_collision(&mut self,e: &mut Enemy) {
-        let hit = rect_collide(e.get_rect(), self.player.get_rect());
+        let hit = e.get_shape().overlaps(&self.player.get_shape());
 
 
 
         if hit && e.can_collide {
-            if e.kind == EnemyType::FollowEnemy {
+            if e.color_contact() {
                 if e.state == self.color_state {
-                self.player.health = min(self.player.max_health, self.player.health + self.player.heal_from_b)
+                    self.heal_player(self.player.heal_from_b);
                 } else {
-                    self.player.health -= 1;
+                    self.damage_player(1, e.archetype);
                 }
             } else {
-                self.player.health -= e.contact_damage;
-                play_sound(&self.assets.hit, PlaySoundParams { looped: false, volume: self.effect_level as f32 / 10.0 });
+                self.damage_player(e.contact_damage, e.archetype);
             }
             e.health = 0.0;
+            // no splitting right on top of the player
+            e.splits = 0;
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn circle(x: f32, y: f32, r: f32) -> Shape {
+        Shape::Circle { x, y, r }
+    }
+
+    fn aabb(x: f32, y: f32, w: f32, h: f32) -> Shape {
+        Shape::Aabb(Rect::new(x, y, w, h))
+    }
+
+    fn capsule(ax: f32, ay: f32, bx: f32, by: f32, r: f32) -> Shape {
+        Shape::Capsule { a: vec2(ax, ay), b: vec2(bx, by), r }
+    }
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    #[test]
+    fn circle_circle() {
+        assert!(circle(0.0, 0.0, 10.0).overlaps(&circle(15.0, 0.0, 10.0)));
+        assert!(!circle(0.0, 0.0, 10.0).overlaps(&circle(25.0, 0.0, 10.0)));
+        // touching isn't overlapping
+        assert!(!circle(0.0, 0.0, 10.0).overlaps(&circle(20.0, 0.0, 10.0)));
+        assert!(circle(0.0, 0.0, 10.0).overlaps(&circle(0.0, 0.0, 1.0)));
+    }
+
+    #[test]
+    fn circle_aabb() {
+        let rect = aabb(0.0, 0.0, 100.0, 50.0);
+        assert!(circle(110.0, 25.0, 15.0).overlaps(&rect));
+        assert!(rect.overlaps(&circle(110.0, 25.0, 15.0)));
+        assert!(!circle(120.0, 25.0, 15.0).overlaps(&rect));
+        assert!(!circle(115.0, 25.0, 15.0).overlaps(&rect));
+        // past a corner it's the diagonal that counts
+        assert!(!circle(110.0, 60.0, 14.0).overlaps(&rect));
+        assert!(circle(110.0, 60.0, 15.0).overlaps(&rect));
+        // fully inside
+        assert!(circle(50.0, 25.0, 1.0).overlaps(&rect));
+    }
+
+    #[test]
+    fn circle_capsule() {
+        let cap = capsule(0.0, 0.0, 100.0, 0.0, 5.0);
+        assert!(circle(50.0, 12.0, 8.0).overlaps(&cap));
+        assert!(cap.overlaps(&circle(50.0, 12.0, 8.0)));
+        assert!(!circle(50.0, 14.0, 8.0).overlaps(&cap));
+        // past the ends it's round
+        assert!(circle(110.0, 0.0, 6.0).overlaps(&cap));
+        assert!(!circle(110.0, 8.0, 6.0).overlaps(&cap));
+    }
+
+    #[test]
+    fn aabb_aabb() {
+        let rect = aabb(0.0, 0.0, 10.0, 10.0);
+        assert!(rect.overlaps(&aabb(5.0, 5.0, 10.0, 10.0)));
+        assert!(!rect.overlaps(&aabb(10.0, 0.0, 10.0, 10.0)));
+        assert!(rect.overlaps(&aabb(2.0, 2.0, 1.0, 1.0)));
+    }
+
+    #[test]
+    fn aabb_capsule() {
+        let rect = aabb(0.0, 0.0, 100.0, 50.0);
+        // passes right through without either end inside
+        assert!(capsule(-50.0, 25.0, 150.0, 25.0, 1.0).overlaps(&rect));
+        assert!(rect.overlaps(&capsule(-50.0, 25.0, 150.0, 25.0, 1.0)));
+        // one end inside
+        assert!(capsule(50.0, 25.0, 200.0, 25.0, 1.0).overlaps(&rect));
+        // runs alongside, the radius decides
+        assert!(capsule(0.0, 55.0, 100.0, 55.0, 6.0).overlaps(&rect));
+        assert!(!capsule(0.0, 55.0, 100.0, 55.0, 4.0).overlaps(&rect));
+        // diagonal past a corner
+        assert!(!capsule(110.0, 40.0, 90.0, 60.0, 1.0).overlaps(&aabb(0.0, 0.0, 95.0, 45.0)));
+    }
+
+    #[test]
+    fn capsule_capsule() {
+        // crossing
+        assert!(capsule(0.0, 0.0, 10.0, 10.0, 0.1).overlaps(&capsule(0.0, 10.0, 10.0, 0.0, 0.1)));
+        // parallel, the radii add up
+        assert!(capsule(0.0, 0.0, 10.0, 0.0, 3.0).overlaps(&capsule(0.0, 5.0, 10.0, 5.0, 3.0)));
+        assert!(!capsule(0.0, 0.0, 10.0, 0.0, 2.0).overlaps(&capsule(0.0, 5.0, 10.0, 5.0, 2.0)));
+        // collinear with a gap
+        assert!(!capsule(0.0, 0.0, 10.0, 0.0, 1.0).overlaps(&capsule(13.0, 0.0, 20.0, 0.0, 1.0)));
+        assert!(capsule(0.0, 0.0, 10.0, 0.0, 2.0).overlaps(&capsule(13.0, 0.0, 20.0, 0.0, 2.0)));
+    }
+
+    #[test]
+    fn zero_length_capsule_is_a_circle() {
+        let cap = capsule(10.0, 10.0, 10.0, 10.0, 5.0);
+        assert!(close(point_segment_distance(vec2(13.0, 14.0), vec2(10.0, 10.0), vec2(10.0, 10.0)), 5.0));
+        assert!(close(cap.distance_to_point(vec2(20.0, 10.0)), 5.0));
+        assert!(close(cap.max_distance_to_point(vec2(20.0, 10.0)), 15.0));
+        assert!(cap.overlaps(&circle(18.0, 10.0, 4.0)));
+        assert!(!cap.overlaps(&circle(20.0, 10.0, 4.0)));
+        assert!(cap.overlaps(&aabb(14.0, 0.0, 10.0, 20.0)));
+        assert!(!cap.overlaps(&aabb(16.0, 0.0, 10.0, 20.0)));
+        assert!(cap.overlaps(&capsule(10.0, 10.0, 10.0, 10.0, 1.0)));
+    }
+
+    #[test]
+    fn distances() {
+        let rect = aabb(0.0, 0.0, 10.0, 10.0);
+        assert!(close(rect.distance_to_point(vec2(5.0, 5.0)), 0.0));
+        assert!(close(rect.distance_to_point(vec2(13.0, 14.0)), 5.0));
+        assert!(close(rect.max_distance_to_point(vec2(0.0, 0.0)), 200f32.sqrt()));
+        assert!(close(circle(0.0, 0.0, 2.0).distance_to_point(vec2(1.0, 0.0)), 0.0));
+        assert!(close(circle(0.0, 0.0, 2.0).max_distance_to_point(vec2(1.0, 0.0)), 3.0));
+        assert!(close(capsule(0.0, 0.0, 10.0, 0.0, 1.0).distance_to_point(vec2(5.0, 4.0)), 3.0));
+        assert!(close(capsule(0.0, 0.0, 10.0, 0.0, 1.0).max_distance_to_point(vec2(0.0, 0.0)), 11.0));
+    }
+
+    #[test]
+    fn rings() {
+        // ring from 90 to 110 around the origin
+        assert!(circle(100.0, 0.0, 1.0).overlaps_ring(0.0, 0.0, 100.0, 20.0));
+        // inside the hole and outside the ring
+        assert!(!circle(0.0, 0.0, 50.0).overlaps_ring(0.0, 0.0, 100.0, 20.0));
+        assert!(!circle(150.0, 0.0, 10.0).overlaps_ring(0.0, 0.0, 100.0, 20.0));
+        // just touching either edge counts
+        assert!(circle(120.0, 0.0, 10.0).overlaps_ring(0.0, 0.0, 100.0, 20.0));
+        assert!(circle(0.0, 0.0, 90.0).overlaps_ring(0.0, 0.0, 100.0, 20.0));
+        // big enough to cover the whole ring
+        assert!(circle(0.0, 0.0, 500.0).overlaps_ring(0.0, 0.0, 100.0, 20.0));
+        // a box straddling the ring, and one sitting in the hole
+        assert!(aabb(80.0, -10.0, 40.0, 20.0).overlaps_ring(0.0, 0.0, 100.0, 20.0));
+        assert!(!aabb(-20.0, -20.0, 40.0, 40.0).overlaps_ring(0.0, 0.0, 100.0, 20.0));
+        // a capsule crossing the ring
+        assert!(capsule(0.0, 0.0, 200.0, 0.0, 1.0).overlaps_ring(0.0, 0.0, 100.0, 20.0));
+        assert!(!capsule(0.0, 0.0, 50.0, 0.0, 1.0).overlaps_ring(0.0, 0.0, 100.0, 20.0));
+    }
+}
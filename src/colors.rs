@@ -14,6 +14,15 @@ impl ColorState {
         }
     }
 }
+// the built in ones at the start of Game::palettes, custom ones come after
+pub const PALETTE_NAMES: [&str; 5] = ["Red & Blue", "Orange & Green", "Purple & Yellow", "Blue & Orange", "Sky & Vermillion"];
+
+// Okabe-Ito colors, they stay apart with the common kinds of color blindness
+pub const CB_BLUE: Color = Color { r: 0.0, g: 0.447, b: 0.698, a: 1.0 };
+pub const CB_ORANGE: Color = Color { r: 0.902, g: 0.624, b: 0.0, a: 1.0 };
+pub const CB_SKY: Color = Color { r: 0.337, g: 0.706, b: 0.914, a: 1.0 };
+pub const CB_VERMILLION: Color = Color { r: 0.835, g: 0.369, b: 0.0, a: 1.0 };
+
 #[derive(Clone, Copy)]
 pub struct ColorPalette {
     pub fg_primary: Color, 
@@ -40,4 +49,138 @@ impl ColorPalette {
             bg_secondary: Color{ r: secondary.r * 0.08, g: secondary.g * 0.08, b: secondary.b * 0.08, a: 1.0},
         }
     }
-}
\ No newline at end of file
+}
+
+// h in degrees, s and v from 0 to 1
+pub fn hsv_to_color(h: f32, s: f32, v: f32) -> Color {
+    let h = h.rem_euclid(360.0) / 60.0;
+    let c = v * s;
+    let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+    let (r, g, b) = match h as i32 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    let m = v - c;
+    Color { r: r + m, g: g + m, b: b + m, a: 1.0 }
+}
+
+pub fn color_to_hsv(c: Color) -> (f32, f32, f32) {
+    let max = c.r.max(c.g).max(c.b);
+    let min = c.r.min(c.g).min(c.b);
+    let d = max - min;
+
+    let h = if d == 0.0 {
+        0.0
+    } else if max == c.r {
+        60.0 * ((c.g - c.b) / d).rem_euclid(6.0)
+    } else if max == c.g {
+        60.0 * ((c.b - c.r) / d + 2.0)
+    } else {
+        60.0 * ((c.r - c.g) / d + 4.0)
+    };
+    let s = if max == 0.0 { 0.0 } else { d / max };
+    (h, s, max)
+}
+
+// relative luminance, the same one contrast checkers use
+pub fn luminance(c: Color) -> f32 {
+    let lin = |v: f32| if v <= 0.04045 { v / 12.92 } else { ((v + 0.055) / 1.055).powf(2.4) };
+    0.2126 * lin(c.r) + 0.7152 * lin(c.g) + 0.0722 * lin(c.b)
+}
+
+pub fn contrast_ratio(a: Color, b: Color) -> f32 {
+    let (la, lb) = (luminance(a), luminance(b));
+    (la.max(lb) + 0.05) / (la.min(lb) + 0.05)
+}
+
+pub fn color_to_hex(c: Color) -> String {
+    let [r, g, b, _]: [u8; 4] = c.into();
+    format!("{:02x}{:02x}{:02x}", r, g, b)
+}
+
+pub fn color_from_hex(s: &str) -> Option<Color> {
+    if s.len() != 6 {
+        return None;
+    }
+    u32::from_str_radix(s, 16).ok().map(Color::from_hex)
+}
+
+// Custom palettes are saved as primary:secondary hex pairs, backgrounds come from create_from
+pub fn parse_custom_palettes(s: &str) -> Vec<ColorPalette> {
+    s.split(',')
+        .filter_map(|pair| {
+            let (primary, secondary) = pair.split_once(':')?;
+            Some(ColorPalette::create_from(color_from_hex(primary)?, color_from_hex(secondary)?))
+        })
+        .collect()
+}
+
+pub fn custom_palettes_to_string(palettes: &[ColorPalette]) -> String {
+    palettes.iter()
+        .map(|p| format!("{}:{}", color_to_hex(p.fg_primary), color_to_hex(p.fg_secondary)))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+// The chosen palette is saved by name, or custom:slot, so new built in ones don't shift it
+pub fn palette_to_string(idx: usize) -> String {
+    match PALETTE_NAMES.get(idx) {
+        Some(name) => name.to_string(),
+        None => format!("custom:{}", idx - PALETTE_NAMES.len()),
+    }
+}
+
+// None if the palette is gone
+pub fn parse_palette(s: &str, palette_count: usize) -> Option<usize> {
+    let idx = match s.strip_prefix("custom:") {
+        Some(slot) => PALETTE_NAMES.len() + slot.parse::<usize>().ok()?,
+        None => PALETTE_NAMES.iter().position(|n| *n == s)?,
+    };
+    if idx < palette_count { Some(idx) } else { None }
+}
+
+// Color blind mode marks every state with a shape too, a plus for primary and a cross for secondary
+pub fn draw_state_glyph(state: ColorState, x: f32, y: f32, size: f32, color: Color) {
+    let r = size / 2.0;
+    let thickness = (size * 0.2).max(2.0);
+    match state {
+        ColorState::Primary => {
+            draw_line(x - r, y, x + r, y, thickness, color);
+            draw_line(x, y - r, x, y + r, thickness, color);
+        }
+        ColorState::Secondary => {
+            let d = r * 0.75;
+            draw_line(x - d, y - d, x + d, y + d, thickness, color);
+            draw_line(x - d, y + d, x + d, y - d, thickness, color);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn palette_round_trip() {
+        let count = PALETTE_NAMES.len() + 2;
+        for idx in 0..count {
+            assert_eq!(parse_palette(&palette_to_string(idx), count), Some(idx));
+        }
+        assert_eq!(palette_to_string(1), "Orange & Green");
+        assert_eq!(palette_to_string(PALETTE_NAMES.len() + 1), "custom:1");
+    }
+
+    #[test]
+    fn bad_palette_saves() {
+        let count = PALETTE_NAMES.len();
+        assert_eq!(parse_palette("custom:0", count), None);
+        assert_eq!(parse_palette("custom:x", count), None);
+        assert_eq!(parse_palette("Green & Red", count), None);
+        assert_eq!(parse_palette("", count), None);
+        assert_eq!(parse_palette("0", count), None);
+    }
+}
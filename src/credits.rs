@@ -0,0 +1,112 @@
+use macroquad::prelude::*;
+use macroquad::logging::error;
+
+use crate::game::*;
+
+// pixels per second
+pub const CREDITS_SCROLL_SPEED: f32 = 40.0;
+pub const CREDITS_MANUAL_SPEED: f32 = 400.0;
+
+const HEADING_GAP: f32 = 110.0;
+const LINE_GAP: f32 = 45.0;
+const BLANK_GAP: f32 = 30.0;
+
+pub enum CreditLine {
+    Heading(String),
+    Text(String),
+    Blank,
+}
+
+impl CreditLine {
+    fn height(&self) -> f32 {
+        match self {
+            CreditLine::Heading(_) => HEADING_GAP,
+            CreditLine::Text(_) => LINE_GAP,
+            CreditLine::Blank => BLANK_GAP,
+        }
+    }
+}
+
+fn parse_credit_line(line: &str) -> Result<CreditLine, String> {
+    let line = line.trim();
+    if line.is_empty() {
+        return Ok(CreditLine::Blank);
+    }
+    match line.strip_prefix('[') {
+        Some(rest) => rest.strip_suffix(']')
+            .map(|h| CreditLine::Heading(h.trim().to_string()))
+            .ok_or(format!("unclosed heading '{}'", line)),
+        None => Ok(CreditLine::Text(line.to_string())),
+    }
+}
+
+pub fn parse_credits(data: &str) -> Vec<CreditLine> {
+    data.lines()
+        .filter(|l| !l.trim_start().starts_with('#'))
+        .filter_map(|l| match parse_credit_line(l) {
+            Ok(line) => Some(line),
+            Err(e) => {
+                error!("CREDITS.txt: {}", e);
+                None
+            }
+        })
+        .collect()
+}
+
+impl Game {
+    fn credits_height(&self) -> f32 {
+        self.credits.iter().map(|l| l.height()).sum()
+    }
+
+    pub fn credits_update(&mut self) {
+        self.background_update();
+        let up = is_key_down(KeyCode::W) || is_key_down(KeyCode::Up);
+        let down = is_key_down(KeyCode::S) || is_key_down(KeyCode::Down);
+
+        // rolls on its own, W/S to move it along or back
+        let speed = if up {
+            -CREDITS_MANUAL_SPEED
+        } else if down {
+            CREDITS_MANUAL_SPEED
+        } else {
+            CREDITS_SCROLL_SPEED
+        };
+        self.credits_scroll += speed * get_frame_time();
+
+        // starts just under the screen and loops once it's all gone past the top
+        let end = self.credits_height();
+        if self.credits_scroll > end {
+            self.credits_scroll = -DESIGN_HEIGHT;
+        }
+        self.credits_scroll = self.credits_scroll.max(-DESIGN_HEIGHT);
+
+        if is_key_pressed(KeyCode::Escape) {
+            self.game_state = GameState::MainMenu;
+            self.menu_switch();
+        }
+    }
+
+    pub fn credits_draw(&mut self) {
+        clear_background(BLACK);
+        self.background_draw();
+
+        let x_center = DESIGN_WIDTH / 2.0;
+        let mut y = -self.credits_scroll;
+        for line in self.credits.iter() {
+            y += line.height();
+            if !(-HEADING_GAP..=DESIGN_HEIGHT + HEADING_GAP).contains(&y) {
+                continue;
+            }
+            match line {
+                CreditLine::Heading(h) => draw_text_centered_c(h, x_center, y, 18.0, &self.assets.font_monogram, self.palette.fg_primary),
+                CreditLine::Text(t) => draw_text_centered(t, x_center, y, 8.0, &self.assets.font_monogram),
+                CreditLine::Blank => (),
+            }
+        }
+
+        draw_text_ex("W/S - scroll", 50.0, 840.0,
+            TextParams { font: Some(&self.assets.font_monogram), font_size: 7, color: GRAY, ..Default::default()});
+        draw_text_ex("ESCAPE - return", 50.0, 860.0,
+            TextParams { font: Some(&self.assets.font_monogram), font_size: 7, color: GRAY, ..Default::default()});
+    }
+}
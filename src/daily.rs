@@ -0,0 +1,214 @@
+use std::cell::Cell;
+
+use macroquad::prelude::*;
+use crate::game::*;
+use crate::survival::*;
+use crate::characters::{CharacterKind, difficulty_name};
+use crate::rules::*;
+
+// how many days of daily bests are kept around
+pub const DAILY_HISTORY: usize = 7;
+
+pub fn today() -> i64 {
+    (miniquad::date::now() / 86400.0).floor() as i64
+}
+
+const SPLITMIX_GAMMA: u64 = 0x9E3779B97F4A7C15;
+
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
+
+// splitmix64, so neighbouring days don't get similar seeds
+pub fn daily_seed(day: i64) -> u64 {
+    splitmix64((day as u64).wrapping_add(SPLITMIX_GAMMA))
+}
+
+// Every roll that plays out in a run, from spawns and generated waves to upgrades, blinks and
+// bullet spread. Kept apart from the global rng so screen shake can't change the daily between players
+pub struct RunRng {
+    state: Cell<u64>,
+}
+
+impl RunRng {
+    pub fn new(seed: u64) -> Self {
+        RunRng { state: Cell::new(seed) }
+    }
+
+    pub fn seed(&self, seed: u64) {
+        self.state.set(seed);
+    }
+
+    fn next(&self) -> u64 {
+        let state = self.state.get().wrapping_add(SPLITMIX_GAMMA);
+        self.state.set(state);
+        splitmix64(state)
+    }
+
+    // in [0, n)
+    pub fn below(&self, n: usize) -> usize {
+        (self.next() % n.max(1) as u64) as usize
+    }
+
+    // in [low, high)
+    pub fn range(&self, low: f32, high: f32) -> f32 {
+        let t = (self.next() >> 40) as f32 / (1u64 << 24) as f32;
+        low + t * (high - low)
+    }
+}
+
+// The player's own picks, put back once the daily is over
+pub struct SavedSetup {
+    pub character: i32,
+    pub difficulty: i32,
+    pub polarity: bool,
+    pub mutators: Vec<Mutator>,
+}
+
+// Everything about the day's run that isn't left to the player
+pub struct DailyChallenge {
+    pub day: i64,
+    pub seed: u64,
+    pub character: usize,
+    pub difficulty: i32,
+    pub polarity: bool,
+    pub mutators: Vec<Mutator>,
+}
+
+impl DailyChallenge {
+    pub fn for_day(day: i64, playable_characters: usize) -> Self {
+        let seed = daily_seed(day);
+
+        // one mutator a day, sometimes a second one
+        let mut mutators = vec![MUTATORS[((seed >> 24) % MUTATORS.len() as u64) as usize]];
+        let second = MUTATORS[((seed >> 32) % MUTATORS.len() as u64) as usize];
+        if (seed >> 40).is_multiple_of(3) && !mutators.contains(&second) {
+            mutators.push(second);
+        }
+
+        DailyChallenge {
+            day,
+            seed,
+            character: (seed % playable_characters.max(1) as u64) as usize,
+            difficulty: ((seed >> 8) % 3) as i32,
+            polarity: (seed >> 16).is_multiple_of(2),
+            mutators,
+        }
+    }
+}
+
+pub struct DailyRecord {
+    pub day: i64,
+    pub score: i32,
+    pub mutators: Vec<Mutator>,
+}
+
+// Saved as day:score:mutators, newest first
+pub fn parse_daily_best(s: &str) -> Vec<DailyRecord> {
+    s.split(',')
+        .filter_map(|entry| {
+            let mut parts = entry.splitn(3, ':');
+            Some(DailyRecord {
+                day: parts.next()?.parse().ok()?,
+                score: parts.next()?.parse().ok()?,
+                mutators: parse_mutators(parts.next().unwrap_or("")),
+            })
+        })
+        .collect()
+}
+
+pub fn daily_best_to_string(table: &[DailyRecord]) -> String {
+    table.iter()
+        .map(|r| format!("{}:{}:{}", r.day, r.score, mutators_to_string(&r.mutators)))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+impl Game {
+    pub fn daily_challenge(&self) -> DailyChallenge {
+        let playable = self.characters.iter().filter(|c| c.kind != CharacterKind::Locked).count();
+        DailyChallenge::for_day(today(), playable)
+    }
+
+    pub fn daily_played_today(&self) -> bool {
+        self.daily_last_day == today()
+    }
+
+    pub fn start_daily(&mut self) {
+        if self.daily_played_today() {
+            return;
+        }
+
+        let daily = self.daily_challenge();
+        self.saved_setup = Some(SavedSetup {
+            character: self.selected_char,
+            difficulty: self.difficulty_select,
+            polarity: self.rules.polarity,
+            mutators: self.rules.mutators.clone(),
+        });
+        self.mode = GameMode::Daily;
+        self.selected_char = daily.character as i32;
+        self.difficulty_select = daily.difficulty;
+        self.rules.polarity = daily.polarity;
+        self.rules.mutators = daily.mutators.clone();
+        self.run_rng.seed(daily.seed);
+
+        // the attempt counts as soon as it starts, quitting doesn't give a retry
+        self.daily_last_day = daily.day;
+        self.should_save = true;
+        self.start_run();
+    }
+
+    pub fn end_daily(&mut self) {
+        if let Some(setup) = self.saved_setup.take() {
+            self.selected_char = setup.character;
+            self.difficulty_select = setup.difficulty;
+            self.rules.polarity = setup.polarity;
+            self.rules.mutators = setup.mutators;
+        }
+    }
+
+    // true if it beat the best for today
+    pub fn record_daily_score(&mut self) -> bool {
+        // the day the run was seeded for, it may have gone past midnight since
+        let day = self.daily_last_day;
+        let record = DailyRecord { day, score: self.current_score, mutators: self.rules.mutators.clone() };
+        let best = match self.daily_best.iter_mut().find(|r| r.day == day) {
+            Some(r) if r.score >= record.score => false,
+            Some(r) => { *r = record; true }
+            None => { self.daily_best.insert(0, record); true }
+        };
+        self.daily_best.sort_by_key(|r| std::cmp::Reverse(r.day));
+        self.daily_best.truncate(DAILY_HISTORY);
+        self.should_save = true;
+        best
+    }
+
+    pub fn daily_draw(&self, x: f32, y: f32) {
+        let daily = self.daily_challenge();
+        let c = &self.characters[daily.character];
+        let rules = if daily.polarity { "Polarity rules" } else { "Classic rules" };
+
+        draw_text_centered("Today", x, y, 10.0, &self.assets.font_monogram);
+        draw_text_centered(&c.name, x, y + 40.0, 8.0, &self.assets.font_monogram);
+        draw_text_centered(difficulty_name(daily.difficulty), x, y + 70.0, 8.0, &self.assets.font_monogram);
+        draw_text_centered(rules, x, y + 100.0, 8.0, &self.assets.font_monogram);
+        draw_text_centered(&mutators_name(&daily.mutators), x, y + 130.0, 6.0, &self.assets.font_monogram);
+        if self.daily_played_today() {
+            draw_text_centered("Come back tomorrow", x, y + 170.0, 8.0, &self.assets.font_monogram);
+        }
+
+        draw_text_centered("Daily bests", x, y + 220.0, 10.0, &self.assets.font_monogram);
+        for (i, r) in self.daily_best.iter().enumerate() {
+            let ago = match today() - r.day {
+                0 => String::from("today"),
+                1 => String::from("yesterday"),
+                n => format!("{} days ago", n),
+            };
+            draw_text_centered(&format!("{}: {}", ago, r.score), x, y + 260.0 + i as f32 * 30.0, 7.0, &self.assets.font_monogram);
+        }
+    }
+}
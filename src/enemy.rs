@@ -2,16 +2,43 @@ use macroquad::prelude::*;
 use crate::game::*;
 use crate::bullet::*;
 use crate::colors::*;
+use crate::collision::Shape;
+use crate::laser::Laser;
 
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub enum EnemyType {
     FollowEnemy,
     FollowShootEnemy,
     StaticCircleAttack,
+    PatternShooter,
+    Charger,
+    Splitter,
+    Teleporter,
+    Shielded,
+    Sniper,
 }
 
+// charger phases
+pub const CHARGER_APPROACH: i32 = 0;
+pub const CHARGER_TELEGRAPH: i32 = 1;
+pub const CHARGER_DASH: i32 = 2;
+
+pub const SPLITTER_CHILD_HEALTH: f32 = 2.0;
+
+// How an enemy changes color on its own
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum FlipMode {
+    Never,
+    Timer,    // every flip_tmax seconds
+    OnSwitch, // shortly after the player switches
+}
+
+// seconds of warning before any flip
+pub const FLIP_WINDUP: f32 = 0.6;
+
 #[derive(Clone, Copy)]
 pub struct Enemy {
+    pub id: u32,
     pub health: f32,
     pub x: f32,
     pub y: f32,
@@ -23,11 +50,29 @@ pub struct Enemy {
     pub can_collide: bool,
     pub contact_damage: i32,
     pub attack_t: f32,
+    pub pattern: Option<usize>,
+    pub emit_angle: f32,
+    pub emit_count: u32,
+    pub bullet_color: BulletColor,
+    pub immune: Option<ColorState>,
+
+    // per archetype state
+    pub timer: f32,
+    pub phase: i32,
+    pub dir: Vec2,    // charger dash direction, shield facing
+    pub anchor: Vec2, // where a teleporter blinked from
+    pub splits: i32,  // how many more times a splitter divides
+    pub archetype: Option<usize>, // bestiary entry, for kill counts
+
+    pub flip: FlipMode,
+    pub flip_t: f32,    // time until the next flip, negative when nothing is queued
+    pub flip_tmax: f32,
 }
 
 impl Default for Enemy {
     fn default() -> Self {
         Self {
+            id: 0,
             health: 10.0,
             x: 50.0,
             y: 50.0,
@@ -39,6 +84,20 @@ impl Default for Enemy {
             attack_speed: 0.0,
             attack_t: 0.0,
             contact_damage: 2,
+            pattern: None,
+            emit_angle: 0.0,
+            emit_count: 0,
+            bullet_color: BulletColor::Shooter,
+            immune: None,
+            timer: 0.0,
+            phase: 0,
+            dir: Vec2::X,
+            anchor: Vec2::ZERO,
+            splits: 0,
+            archetype: None,
+            flip: FlipMode::Never,
+            flip_t: -1.0,
+            flip_tmax: 3.0,
         }
     }
 }
@@ -52,34 +111,109 @@ impl Enemy {
             h: self.size,
         }
     }
+
+    pub fn get_shape(&self) -> Shape {
+        Shape::Aabb(self.get_rect())
+    }
+
+    pub fn center(&self) -> Vec2 {
+        Vec2 { x: self.x + self.size / 2.0, y: self.y + self.size / 2.0 }
+    }
+
+    // touching it heals or hurts based on color, instead of flat contact damage
+    pub fn color_contact(&self) -> bool {
+        self.kind == EnemyType::FollowEnemy || self.flip != FlipMode::Never
+    }
+
+    pub fn flip_windup(&self) -> bool {
+        self.flip != FlipMode::Never && self.flip_t >= 0.0 && self.flip_t < FLIP_WINDUP
+    }
+
+    // shielded enemies ignore anything hitting the side they face
+    pub fn blocks(&self, from: Vec2) -> bool {
+        self.kind == EnemyType::Shielded && (from - self.center()).dot(self.dir) > 0.0
+    }
+
+    // smaller copies left behind when a splitter dies, one of each color
+    pub fn split_children(&self) -> Vec<Enemy> {
+        if self.kind != EnemyType::Splitter || self.splits <= 0 {
+            return Vec::new();
+        }
+
+        let mut children = Vec::new();
+        for (i, state) in [self.state, self.state.next()].into_iter().enumerate() {
+            let size = self.size * 0.7;
+            let offset = if i == 0 { -size / 2.0 } else { size / 2.0 };
+            children.push(Enemy {
+                x: self.x + self.size / 2.0 - size / 2.0 + offset,
+                y: self.y + self.size / 2.0 - size / 2.0,
+                size,
+                health: SPLITTER_CHILD_HEALTH,
+                score: self.score / 2,
+                state,
+                splits: self.splits - 1,
+                id: 0,
+                ..*self
+            });
+        }
+        children
+    }
+}
+
+// The built in enemies, ARCHETYPES in script.rs names them by position
+pub fn default_enemies() -> [Enemy; 17] {
+    [
+        Enemy { health: 5.0, x: 50.0, y: 50.0, size: 40.0, score: 15 , kind: EnemyType::FollowShootEnemy, attack_speed: 1.0, can_collide: true, bullet_color: BulletColor::OppositePlayer, ..Default::default()},
+        Enemy { health: 10.0, x: 50.0, y: 50.0, size: 40.0, score: 30, kind: EnemyType::StaticCircleAttack, can_collide: true, contact_damage: 3, attack_t: 5.0, attack_speed: 5.0, ..Default::default()},
+        Enemy { state: ColorState::Primary,health: 2.0, x: 50.0, y: 50.0, size: 20.0, score: 5, kind: EnemyType::FollowEnemy, can_collide: true, ..Default::default()},
+        Enemy { state: ColorState::Secondary,health: 2.0, x: 50.0, y: 50.0, size: 20.0, score: 5, kind: EnemyType::FollowEnemy, can_collide: true, ..Default::default()},
+        Enemy { state: ColorState::Secondary,health: 2.0, x: 50.0, y: 50.0, size: 20.0, score: 5, kind: EnemyType::FollowEnemy, can_collide: true, ..Default::default()},
+        Enemy { state: ColorState::Secondary,health: 2.0, x: 50.0, y: 50.0, size: 20.0, score: 5, kind: EnemyType::FollowEnemy, can_collide: true, ..Default::default()},
+        Enemy { state: ColorState::Secondary,health: 2.0, x: 50.0, y: 50.0, size: 20.0, score: 5, kind: EnemyType::FollowEnemy, can_collide: true, ..Default::default()},
+        Enemy { state: ColorState::Secondary,health: 2.0, x: 50.0, y: 50.0, size: 20.0, score: 5, kind: EnemyType::FollowEnemy, can_collide: true, ..Default::default()},
+        Enemy { state: ColorState::Secondary,health: 2.0, x: 50.0, y: 50.0, size: 20.0, score: 5, kind: EnemyType::FollowEnemy, can_collide: true, ..Default::default()},
+        Enemy { state: ColorState::Secondary,health: 2.0, x: 50.0, y: 50.0, size: 20.0, score: 5, kind: EnemyType::FollowEnemy, can_collide: true, ..Default::default()},
+        Enemy { state: ColorState::Primary, health: 4.0, size: 40.0, score: 20, kind: EnemyType::Charger, can_collide: true, contact_damage: 2, timer: 1.0, ..Default::default()},
+        Enemy { state: ColorState::Secondary, health: 6.0, size: 40.0, score: 20, kind: EnemyType::Splitter, can_collide: true, contact_damage: 1, splits: 2, ..Default::default()},
+        Enemy { state: ColorState::Primary, health: 4.0, size: 40.0, score: 25, kind: EnemyType::Teleporter, can_collide: true, attack_speed: 2.0, attack_t: 2.0, bullet_color: BulletColor::OppositePlayer, ..Default::default()},
+        Enemy { state: ColorState::Secondary, health: 8.0, size: 40.0, score: 30, kind: EnemyType::Shielded, can_collide: true, contact_damage: 2, ..Default::default()},
+        Enemy { state: ColorState::Primary, health: 3.0, size: 25.0, score: 10, kind: EnemyType::FollowEnemy, can_collide: true, flip: FlipMode::Timer, flip_tmax: 2.5, ..Default::default()},
+        Enemy { state: ColorState::Secondary, health: 3.0, size: 25.0, score: 10, kind: EnemyType::FollowEnemy, can_collide: true, flip: FlipMode::OnSwitch, ..Default::default()},
+        Enemy { state: ColorState::Primary, health: 5.0, size: 40.0, score: 35, kind: EnemyType::Sniper, can_collide: true, attack_speed: 4.0, attack_t: 2.0, bullet_color: BulletColor::OppositePlayer, ..Default::default()},
+    ]
 }
 
 impl Game {
     pub fn update_follow_enemy(&mut self,e: &mut Enemy) {
-        let dt = get_frame_time();
-        let dir = dir_to_player(e.x, e.y, &self.player);
-        let speed = 250.0;
+        let dt = self.world_dt();
+        let dir = self.steer(e);
+        let speed = 250.0 * self.enemy_speed_mult();
 
         e.x += dir.x * speed * dt;
         e.y += dir.y * speed * dt;
     }
 
     pub fn draw_follow_enemy(&mut self,e: &mut Enemy) {
-        let color = match e.state {
+        let mut color = match e.state {
             ColorState::Primary => self.palette.fg_primary,
             ColorState::Secondary => self.palette.fg_secondary,
         };
 
+        // flicker while winding up a flip
+        if e.flip_windup() && (e.flip_t * 15.0) as i32 % 2 == 0 {
+            color = WHITE;
+        }
+
         draw_rectangle(e.x, e.y, e.size, e.size, color); 
     }
 
     pub fn update_follow_shoot_enemy(&mut self, e: &mut Enemy) {
         match self.color_state {
             ColorState::Primary => {
-                // Chase player
-                let dt = get_frame_time();
-                let dir = dir_to_player(e.x, e.y, &self.player);
-                let speed = 200.0;
+                // Chase player, keeping some distance
+                let dt = self.world_dt();
+                let dir = self.steer(e);
+                let speed = 200.0 * self.enemy_speed_mult();
                 e.x += dir.x * speed * dt;
                 e.y += dir.y * speed * dt;
             }
@@ -87,9 +221,10 @@ impl Game {
                 // Stop and shoot at player
                 if e.attack_t <= 0.0 {
                     let dir = dir_to_player(e.x, e.y, &self.player);
-                    self.bullets.push(
-                        Bullet::new(1,e.x + e.size/2.0, e.y + e.size/2.0, dir.x, dir.y, 6.0, 550.0, BulletType::Enemy)
-                    );
+                    let mut b = Bullet::new(e.x + e.size/2.0, e.y + e.size/2.0, dir, BulletType::Enemy).with_speed(550.0);
+                    b.state = self.resolve_bullet_color(e.bullet_color, e.state);
+                    b.source = e.archetype;
+                    self.bullets.push(b);
                     e.attack_t = e.attack_speed;
                 }
 
@@ -105,7 +240,7 @@ impl Game {
     pub fn update_static_circle_enemy(&mut self,e: &mut Enemy) {
         let state = self.color_state.next();
 
-        e.attack_t -= get_frame_time();
+        e.attack_t -= self.world_dt();
         if e.attack_t <= 0.0 {
             self.circle_attacks.push(
                 CircleAttack { 
@@ -114,6 +249,7 @@ impl Game {
                     radius: 1.0, 
                     color: state,
                     hit: false,
+                    source: e.archetype,
                 }
             );
             e.attack_t = e.attack_speed;
@@ -124,4 +260,270 @@ impl Game {
         draw_texture(&self.assets.tower, e.x, e.y, WHITE);
         // draw_rectangle(e.x, e.y, e.size, e.size, YELLOW); 
     }
-}
\ No newline at end of file
+
+    pub fn update_pattern_enemy(&mut self, e: &mut Enemy) {
+        // drift in until close enough, then just keep firing
+        let dt = self.world_dt();
+        let dir = self.steer(e);
+        let speed = 80.0 * self.enemy_speed_mult();
+        e.x += dir.x * speed * dt;
+        e.y += dir.y * speed * dt;
+
+        if let Some(pattern) = e.pattern {
+            if e.attack_t <= 0.0 {
+                self.emit_pattern(e, pattern);
+                e.attack_t = e.attack_speed;
+            }
+        }
+    }
+
+    pub fn draw_pattern_enemy(&mut self, e: &mut Enemy) {
+        let color = match e.state {
+            ColorState::Primary => self.palette.fg_primary,
+            ColorState::Secondary => self.palette.fg_secondary,
+        };
+
+        draw_texture_ex(&self.assets.shooter, e.x, e.y, color,
+            DrawTextureParams { dest_size: Some(Vec2 { x: e.size, y: e.size }), ..Default::default() });
+    }
+
+    // sprite used for an archetype, plain squares have none
+    pub fn enemy_sprite(&self, kind: EnemyType) -> Option<&Texture2D> {
+        match kind {
+            EnemyType::FollowEnemy => None,
+            EnemyType::FollowShootEnemy | EnemyType::PatternShooter => Some(&self.assets.shooter),
+            EnemyType::StaticCircleAttack => Some(&self.assets.tower),
+            EnemyType::Charger => Some(&self.assets.rush),
+            EnemyType::Splitter => Some(&self.assets.splitter),
+            EnemyType::Teleporter => Some(&self.assets.teleporter),
+            EnemyType::Shielded => Some(&self.assets.shielded),
+            EnemyType::Sniper => Some(&self.assets.sniper),
+        }
+    }
+
+    pub fn update_color_flip(&mut self, e: &mut Enemy) {
+        match e.flip {
+            FlipMode::Never => return,
+            FlipMode::Timer => {
+                if e.flip_t < 0.0 {
+                    e.flip_t = e.flip_tmax;
+                }
+            }
+            FlipMode::OnSwitch => {
+                if self.player_switched && e.flip_t < 0.0 {
+                    e.flip_t = FLIP_WINDUP;
+                }
+            }
+        }
+
+        if e.flip_t >= 0.0 {
+            e.flip_t -= self.world_dt();
+            if e.flip_t < 0.0 {
+                e.state = e.state.next();
+            }
+        }
+    }
+
+    // ring in the next color closing in on the enemy right before it flips
+    pub fn draw_color_flip(&mut self, e: &Enemy) {
+        if !e.flip_windup() {
+            return;
+        }
+
+        let mut next = match e.state.next() {
+            ColorState::Primary => self.palette.fg_primary,
+            ColorState::Secondary => self.palette.fg_secondary,
+        };
+        let t = e.flip_t / FLIP_WINDUP;
+        next.a = 1.0 - t * 0.5;
+
+        let center = e.center();
+        draw_circle_lines(center.x, center.y, e.size * (0.6 + t), 3.0, next);
+    }
+
+    pub fn enemy_color(&self, e: &Enemy) -> Color {
+        match e.state {
+            ColorState::Primary => self.palette.fg_primary,
+            ColorState::Secondary => self.palette.fg_secondary,
+        }
+    }
+
+    pub fn update_charger_enemy(&mut self, e: &mut Enemy) {
+        let dt = self.world_dt();
+        e.timer -= dt;
+
+        match e.phase {
+            CHARGER_TELEGRAPH => {
+                // stand still and lock in on the player
+                if e.timer <= 0.0 {
+                    e.phase = CHARGER_DASH;
+                    e.timer = 0.45;
+                }
+            }
+            CHARGER_DASH => {
+                let speed = 900.0 * self.enemy_speed_mult();
+                e.x += e.dir.x * speed * dt;
+                e.y += e.dir.y * speed * dt;
+                e.x = e.x.clamp(0.0, DESIGN_WIDTH - e.size);
+                e.y = e.y.clamp(0.0, DESIGN_HEIGHT - e.size);
+
+                if e.timer <= 0.0 {
+                    e.phase = CHARGER_APPROACH;
+                    e.timer = 1.2;
+                }
+            }
+            _ => {
+                let dir = self.steer(e);
+                let speed = 140.0 * self.enemy_speed_mult();
+                e.x += dir.x * speed * dt;
+                e.y += dir.y * speed * dt;
+
+                if e.timer <= 0.0 && distance_to_player(e.x, e.y, &self.player) < 350.0 {
+                    e.phase = CHARGER_TELEGRAPH;
+                    e.timer = 0.8;
+                    e.dir = dir_to_player(e.x, e.y, &self.player);
+                }
+            }
+        }
+    }
+
+    pub fn draw_charger_enemy(&mut self, e: &mut Enemy) {
+        let mut color = self.enemy_color(e);
+
+        if e.phase == CHARGER_TELEGRAPH {
+            // show where it's going to go, blinking faster as it gets close
+            let mut line_color = color;
+            line_color.a = 0.4;
+            let center = e.center();
+            draw_line(center.x, center.y, center.x + e.dir.x * 400.0, center.y + e.dir.y * 400.0, e.size, line_color);
+            if (e.timer * 12.0) as i32 % 2 == 0 {
+                color = WHITE;
+            }
+        }
+
+        draw_texture_ex(&self.assets.rush, e.x, e.y, color,
+            DrawTextureParams { dest_size: Some(Vec2 { x: e.size, y: e.size }), ..Default::default() });
+    }
+
+    pub fn update_splitter_enemy(&mut self, e: &mut Enemy) {
+        let dt = self.world_dt();
+        let dir = self.steer(e);
+        let speed = 170.0 * self.enemy_speed_mult();
+        e.x += dir.x * speed * dt;
+        e.y += dir.y * speed * dt;
+    }
+
+    pub fn draw_splitter_enemy(&mut self, e: &mut Enemy) {
+        let color = self.enemy_color(e);
+        draw_texture_ex(&self.assets.splitter, e.x, e.y, color,
+            DrawTextureParams { dest_size: Some(Vec2 { x: e.size, y: e.size }), ..Default::default() });
+    }
+
+    pub fn update_teleporter_enemy(&mut self, e: &mut Enemy) {
+        let dt = self.world_dt();
+        e.timer -= dt;
+
+        // blink somewhere else around the player whenever they switch color
+        if self.player_switched {
+            e.anchor = Vec2 { x: e.x, y: e.y };
+            e.timer = 0.4;
+
+            let angle = self.run_rng.range(0.0, 360.0);
+            let dist = self.run_rng.range(250.0, 400.0);
+            let offset = rotate_vec(Vec2::X, angle) * dist;
+            e.x = (self.player.x + offset.x).clamp(0.0, DESIGN_WIDTH - e.size);
+            e.y = (self.player.y + offset.y).clamp(0.0, DESIGN_HEIGHT - e.size);
+        }
+
+        let dir = self.steer(e);
+        let speed = 120.0 * self.enemy_speed_mult();
+        e.x += dir.x * speed * dt;
+        e.y += dir.y * speed * dt;
+
+        if e.attack_t <= 0.0 {
+            let dir = dir_to_player(e.x, e.y, &self.player);
+            let mut b = Bullet::new(e.x + e.size/2.0, e.y + e.size/2.0, dir, BulletType::Enemy).with_speed(350.0);
+            b.state = self.resolve_bullet_color(e.bullet_color, e.state);
+            b.source = e.archetype;
+            self.bullets.push(b);
+            e.attack_t = e.attack_speed;
+        }
+    }
+
+    pub fn draw_teleporter_enemy(&mut self, e: &mut Enemy) {
+        let color = self.enemy_color(e);
+
+        if e.timer > 0.0 {
+            // fading afterimage where it blinked from
+            let mut ghost = color;
+            ghost.a = e.timer / 0.4 * 0.5;
+            draw_texture_ex(&self.assets.teleporter, e.anchor.x, e.anchor.y, ghost,
+                DrawTextureParams { dest_size: Some(Vec2 { x: e.size, y: e.size }), ..Default::default() });
+        }
+
+        draw_texture_ex(&self.assets.teleporter, e.x, e.y, color,
+            DrawTextureParams { dest_size: Some(Vec2 { x: e.size, y: e.size }), ..Default::default() });
+    }
+
+    pub fn update_shielded_enemy(&mut self, e: &mut Enemy) {
+        let dt = self.world_dt();
+
+        // shield slowly turns to face the player, so flank it
+        let wanted = dir_to_player(e.x, e.y, &self.player);
+        let turn_speed = 70.0;
+        let angle = e.dir.angle_between(wanted).to_degrees();
+        let max_turn = turn_speed * dt;
+        e.dir = rotate_vec(e.dir, angle.clamp(-max_turn, max_turn)).normalize_or_zero();
+
+        let dir = self.steer(e);
+        let speed = 110.0 * self.enemy_speed_mult();
+        e.x += dir.x * speed * dt;
+        e.y += dir.y * speed * dt;
+    }
+
+    pub fn draw_shielded_enemy(&mut self, e: &mut Enemy) {
+        let color = self.enemy_color(e);
+        // the sprite has its shield on the left
+        let rotation = e.dir.y.atan2(e.dir.x) + std::f32::consts::PI;
+
+        draw_texture_ex(&self.assets.shielded, e.x, e.y, color,
+            DrawTextureParams {
+                dest_size: Some(Vec2 { x: e.size, y: e.size }),
+                rotation,
+                ..Default::default()
+            });
+    }
+
+    pub fn update_sniper_enemy(&mut self, e: &mut Enemy) {
+        let dt = self.world_dt();
+
+        // hold still while the shot charges and fires
+        if e.timer > 0.0 {
+            e.timer -= dt;
+            return;
+        }
+
+        let dir = self.steer(e);
+        let speed = 120.0 * self.enemy_speed_mult();
+        e.x += dir.x * speed * dt;
+        e.y += dir.y * speed * dt;
+
+        if e.attack_t <= 0.0 {
+            let center = e.center();
+            let aim = dir_to_player(e.x, e.y, &self.player);
+            let mut l = Laser::new(center.x, center.y, aim, 14.0, 1.2, 0.5, BulletType::Enemy);
+            l.state = self.resolve_bullet_color(e.bullet_color, e.state);
+            l.source = e.archetype;
+            self.lasers.push(l);
+
+            e.timer = 1.7;
+            e.attack_t = e.attack_speed;
+        }
+    }
+
+    pub fn draw_sniper_enemy(&mut self, e: &mut Enemy) {
+        let color = self.enemy_color(e);
+        draw_texture_ex(&self.assets.sniper, e.x, e.y, color,
+            DrawTextureParams { dest_size: Some(Vec2 { x: e.size, y: e.size }), ..Default::default() });
+    }
+}
@@ -1,4 +1,4 @@
-use std::{cmp::min, sync::MutexGuard};
+use std::sync::MutexGuard;
 
 use macroquad::prelude::*;
 use macroquad::audio::*;
@@ -11,6 +11,20 @@ use crate::{assets::Assets, bullet::*, colors::ColorPalette, enemy::*};
 use crate::player::*;
 use crate::wave::*;
 use crate::colors::*;
+use crate::pattern::*;
+use crate::rules::*;
+use crate::steering::*;
+use crate::laser::*;
+use crate::ability::*;
+use crate::script::*;
+use crate::survival::*;
+use crate::daily::*;
+use crate::results::*;
+use crate::achievements::*;
+use crate::bestiary::*;
+use crate::credits::*;
+use crate::palette_editor::*;
+use crate::options::*;
 
 pub const DESIGN_WIDTH: f32 = 1600.;
 pub const DESIGN_HEIGHT: f32 = 900.;
@@ -41,7 +55,12 @@ pub enum GameState {
     Options,
     Collection,
     Characters,
+    Mutators,
     Score,
+    Achievements,
+    Credits,
+    PaletteEditor,
+    FirstLaunch,
 }
 
 
@@ -76,23 +95,54 @@ pub struct Game {
     pub current_score: i32,
     pub assets: Assets,
     pub should_save: bool,
-    pub palettes: [ColorPalette; 3],
-    pub enemy_list:  [Enemy; 10],
-    pub upg_list: [Upgrade; 4],
+    pub palettes: Vec<ColorPalette>, // built in ones first, see PALETTE_NAMES
+    pub enemy_list:  [Enemy; 17],
+    pub patterns: Vec<Pattern>,
+    pub pattern_enemies: Vec<PatternEnemy>,
+    pub waves: Vec<ScriptedWave>,
+    pub wave_runner: ScriptRunner,
+    pub generated_wave: Option<ScriptedWave>,
+    pub mode: GameMode,
+    pub survival: Survival,
+    pub survival_times: Vec<SurvivalRecord>,
+    pub daily_last_day: i64,
+    pub daily_best: Vec<DailyRecord>,
+    pub saved_setup: Option<SavedSetup>,
+    pub run_rng: RunRng,
+    pub high_score_mutators: Vec<Mutator>,
+    pub last_run: Option<RunResult>,
+    pub achievements: Vec<i32>, // progress, lined up with ACHIEVEMENTS
+    pub absorb_streak: i32,
+    pub wave_hit: bool,
+    pub toasts: Vec<Toast>,
+    pub upgrade_picks: Vec<i32>, // lined up with upg_list
+    pub upgrade_seen: Vec<bool>,
+    pub bestiary: Vec<BestiaryEntry>,
+    pub last_hit_by: Option<usize>, // bestiary entry to blame if the run ends
+    pub credits: Vec<CreditLine>,
+    pub credits_scroll: f32,
+    pub upg_list: [Upgrade; 12],
     pub palette: ColorPalette,
-    pub curr_palette_idx: i32,
+    pub curr_palette_idx: i32, // cursor in the palette picker, can sit on a locked one
+    pub chosen_palette: i32,
+    pub palette_editor: PaletteEditor,
+    pub best_wave: i32,
     pub enemy_spawn: Vec<SpawnEnemy>,
     pub enemies: Vec<Enemy>, // Box is for allocating to the heap
+    pub enemy_grid: SpatialGrid,
     pub characters: Vec<Character>,
     pub bullets: Vec<Bullet>,
     pub circle_attacks: Vec<CircleAttack>,
+    pub lasers: Vec<Laser>,
     pub upgrades: Vec<UpgradeEntity>, 
     pub player: Player,
     pub wave: Wave,
     pub upgrade_count: f32,
     pub selected_char: i32,
     pub debug: DebugStuff,
+    pub rules: GameRules,
     pub upgrade_shown: usize,
+    pub enemy_id_counter: u32,
 
     pub menu_bg_x: f32,
     pub menu_bg_y: f32,
@@ -104,25 +154,70 @@ pub struct Game {
     pub effect_level: i32,
     pub collection_x: i32,
     pub shooting_sound: bool,
+    pub colorblind: bool, // shapes on top of colors for every color state
+    pub reduced_motion: bool,
+    pub bg_blend: f32, // 0 primary background, 1 secondary, only used with reduced motion
     pub menu_song_started: bool,
     pub switch_effect_t: f32,
     pub switch_effect_total: f32,
+    pub player_switched: bool,
+    pub time_scale: f32,
+    pub time_slow_t: f32,
+    pub bomb_t: f32,
+    pub shake_t: f32,
 }
 
 impl Game {
     pub async fn default() -> Self {
+        let assets = Assets::default().await;
+        let (patterns, pattern_enemies) = load_patterns(assets.patterns);
+        let waves = load_waves(assets.waves, &archetype_names(&pattern_enemies));
+        let credits = parse_credits(assets.credits);
+
         let mut g = Game {
             color_state: ColorState::Primary,
-            assets: Assets::default().await,
+            assets,
+            patterns,
+            pattern_enemies,
+            waves,
+            wave_runner: ScriptRunner::new(),
+            generated_wave: None,
+            mode: GameMode::Classic,
+            survival: Survival::default(),
+            survival_times: Vec::new(),
+            daily_last_day: -1,
+            daily_best: Vec::new(),
+            saved_setup: None,
+            run_rng: RunRng::new(0),
+            high_score_mutators: Vec::new(),
+            last_run: None,
+            achievements: vec![0; ACHIEVEMENTS.len()],
+            absorb_streak: 0,
+            wave_hit: false,
+            toasts: Vec::new(),
+            upgrade_picks: Vec::new(),
+            upgrade_seen: Vec::new(),
+            bestiary: Vec::new(),
+            last_hit_by: None,
+            credits,
+            credits_scroll: 0.0,
             should_save: false,
             palette: ColorPalette::default(),
             curr_palette_idx: 0,
+            chosen_palette: 0,
+            palette_editor: PaletteEditor::new(&ColorPalette::default(), None),
+            best_wave: 0,
             debug: DebugStuff::default(),
+            rules: GameRules::default(),
             player: Player::default(),
             enemies: Vec::new(),
+            enemy_grid: SpatialGrid::new(100.0),
             enemy_spawn: Vec::new(),
             collection_x: 0,
             shooting_sound: true,
+            colorblind: false,
+            reduced_motion: false,
+            bg_blend: 0.0,
             menu_bg_dx: 30.0,
             menu_bg_dy: 30.0,
             menu_bg_x: -200.0,
@@ -130,8 +225,10 @@ impl Game {
             menu_bg_y: -300.0,
             bullets: vec![],
             circle_attacks: Vec::new(),
+            lasers: Vec::new(),
             characters: Vec::new(),
             upgrade_shown: 1000,
+            enemy_id_counter: 0,
             upgrades: Vec::new(),
             menu_song_started: false,
             high_score: 0,
@@ -151,9 +248,15 @@ impl Game {
 
             switch_effect_t: 0.0,
             switch_effect_total: 0.01,
+            player_switched: false,
+            time_scale: 1.0,
+            time_slow_t: 0.0,
+            bomb_t: 0.0,
+            shake_t: 0.0,
 
             wave: Wave::default(),
 
+            // only ever append, upgrade_picks and upgrade_seen are saved by index
             upg_list: [
                 Upgrade {
                     name: String::from("Talaria"),
@@ -183,6 +286,62 @@ impl Game {
                     kind: UpgradeKind::Speed,
                     rarity: UpgradeRarity::Common,
                 },
+                Upgrade {
+                    name: String::from("Needle"),
+                    description: String::from("Shots pierce;+1 enemy"),
+                    lore: String::from("Sharp enough to go through twice"),
+                    kind: UpgradeKind::Pierce,
+                    rarity: UpgradeRarity::Rare,
+                },
+                Upgrade {
+                    name: String::from("Rubber ammo"),
+                    description: String::from("Shots bounce;off walls;+2 times"),
+                    lore: String::from("Boing"),
+                    kind: UpgradeKind::Bounce,
+                    rarity: UpgradeRarity::Common,
+                },
+                Upgrade {
+                    name: String::from("Seeker"),
+                    description: String::from("Shots curve;towards enemies"),
+                    lore: String::from("It knows where you want it to go"),
+                    kind: UpgradeKind::Homing,
+                    rarity: UpgradeRarity::Epic,
+                },
+                Upgrade {
+                    name: String::from("Shrapnel"),
+                    description: String::from("Shots split;in 2 on hit"),
+                    lore: String::from("Sharing is caring"),
+                    kind: UpgradeKind::Split,
+                    rarity: UpgradeRarity::Rare,
+                },
+                Upgrade {
+                    name: String::from("Rocket fuel"),
+                    description: String::from("Shots speed up;-range"),
+                    lore: String::from("Do not light indoors"),
+                    kind: UpgradeKind::Accelerate,
+                    rarity: UpgradeRarity::Legendary,
+                },
+                Upgrade {
+                    name: String::from("Prism"),
+                    description: String::from("Shoot a laser;instead;+damage"),
+                    lore: String::from("Pew pew, but longer"),
+                    kind: UpgradeKind::Laser,
+                    rarity: UpgradeRarity::Epic,
+                },
+                Upgrade {
+                    name: String::from("Hourglass"),
+                    description: String::from("-25% ability;cooldown"),
+                    lore: String::from("Sand runs faster when you're not looking"),
+                    kind: UpgradeKind::Cooldown,
+                    rarity: UpgradeRarity::Common,
+                },
+                Upgrade {
+                    name: String::from("Spare battery"),
+                    description: String::from("+1 ability;charge;+power"),
+                    lore: String::from("AA not included"),
+                    kind: UpgradeKind::AbilityCharge,
+                    rarity: UpgradeRarity::Rare,
+                },
                 // CollectibeKind::Maxhp,
                 // CollectibeKind::Projectile,
                 // CollectibeKind::Size,
@@ -190,28 +349,20 @@ impl Game {
                 // CollectibeKind::Speed,
             ],
 
-            palettes: [
+            palettes: vec![
                 ColorPalette::default(),
                 ColorPalette::create_from(ORANGE, GREEN),
-                ColorPalette::create_from(PURPLE, YELLOW)
+                ColorPalette::create_from(PURPLE, YELLOW),
+                ColorPalette::create_from(CB_BLUE, CB_ORANGE),
+                ColorPalette::create_from(CB_SKY, CB_VERMILLION),
             ],
 
-            enemy_list: [
-                Enemy { health: 5.0, x: 50.0, y: 50.0, size: 40.0, score: 15 , kind: EnemyType::FollowShootEnemy, attack_speed: 1.0, can_collide: true, ..Default::default()},
-                Enemy { health: 10.0, x: 50.0, y: 50.0, size: 40.0, score: 30, kind: EnemyType::StaticCircleAttack, can_collide: true, contact_damage: 3, attack_t: 5.0, attack_speed: 5.0, ..Default::default()},
-                Enemy { state: ColorState::Primary,health: 2.0, x: 50.0, y: 50.0, size: 20.0, score: 5, kind: EnemyType::FollowEnemy, can_collide: true, ..Default::default()},
-                Enemy { state: ColorState::Secondary,health: 2.0, x: 50.0, y: 50.0, size: 20.0, score: 5, kind: EnemyType::FollowEnemy, can_collide: true, ..Default::default()},
-                Enemy { state: ColorState::Secondary,health: 2.0, x: 50.0, y: 50.0, size: 20.0, score: 5, kind: EnemyType::FollowEnemy, can_collide: true, ..Default::default()},
-                Enemy { state: ColorState::Secondary,health: 2.0, x: 50.0, y: 50.0, size: 20.0, score: 5, kind: EnemyType::FollowEnemy, can_collide: true, ..Default::default()},
-                Enemy { state: ColorState::Secondary,health: 2.0, x: 50.0, y: 50.0, size: 20.0, score: 5, kind: EnemyType::FollowEnemy, can_collide: true, ..Default::default()},
-                Enemy { state: ColorState::Secondary,health: 2.0, x: 50.0, y: 50.0, size: 20.0, score: 5, kind: EnemyType::FollowEnemy, can_collide: true, ..Default::default()},
-                Enemy { state: ColorState::Secondary,health: 2.0, x: 50.0, y: 50.0, size: 20.0, score: 5, kind: EnemyType::FollowEnemy, can_collide: true, ..Default::default()},
-                Enemy { state: ColorState::Secondary,health: 2.0, x: 50.0, y: 50.0, size: 20.0, score: 5, kind: EnemyType::FollowEnemy, can_collide: true, ..Default::default()},
-            ]
+            enemy_list: default_enemies(),
         };
         g.characters = vec![
             Character {
                p: Player::default(),
+               ability: AbilityKind::Dash,
                name: String::from("Garry"),
                health: 10,
                speed: 10.0,
@@ -221,6 +372,7 @@ impl Game {
             },
             Character {
                p: Player::default(),
+               ability: AbilityKind::Bomb,
                name: String::from("Bob, Bob & Bob"),
                health: 10,
                speed: 10.0,
@@ -230,6 +382,7 @@ impl Game {
             },
             Character {
                p: Player::default(),
+               ability: AbilityKind::Shield,
                name: String::from("John"),
                health: 10,
                max_diff: 1,
@@ -239,6 +392,7 @@ impl Game {
             },
             Character {
                p: Player::default(),
+               ability: AbilityKind::TimeSlow,
                name: String::from("Mark"),
                health: 10,
                max_diff: 0,
@@ -248,6 +402,7 @@ impl Game {
             },
             Character {
                p: Player::default(),
+               ability: AbilityKind::Dash,
                name: String::from("Locked"),
                health: 10,
                max_diff: 1,
@@ -257,6 +412,7 @@ impl Game {
             },
             Character {
                p: Player::default(),
+               ability: AbilityKind::Dash,
                name: String::from("Locked"),
                health: 10,
                max_diff: 1,
@@ -265,6 +421,9 @@ impl Game {
                kind: CharacterKind::Locked,
             },
         ];
+        g.upgrade_picks = vec![0; g.upg_list.len()];
+        g.upgrade_seen = vec![false; g.upg_list.len()];
+        g.bestiary = g.build_bestiary();
 
         return g;    
     }
@@ -286,8 +445,14 @@ impl Game {
             GameState::Options => self.settings_update(),
             GameState::Characters => self.characters_update(),
             GameState::Collection => self.collection_update(),
-            _ => ()
+            GameState::Mutators => self.mutators_update(),
+            GameState::Score => self.score_update(),
+            GameState::Achievements => self.achievements_update(),
+            GameState::Credits => self.credits_update(),
+            GameState::PaletteEditor => self.palette_editor_update(),
+            GameState::FirstLaunch => self.first_launch_update(),
         }
+        self.toasts_update();
     }
 
     pub fn draw(&mut self) {
@@ -297,8 +462,14 @@ impl Game {
             GameState::Options => self.settings_draw(BLACK),
             GameState::Characters => self.characters_draw(),
             GameState::Collection => self.collection_draw(),
-            _ => ()
+            GameState::Mutators => self.mutators_draw(),
+            GameState::Score => self.score_draw(),
+            GameState::Achievements => self.achievements_draw(),
+            GameState::Credits => self.credits_draw(),
+            GameState::PaletteEditor => self.palette_editor_draw(),
+            GameState::FirstLaunch => self.first_launch_draw(),
         }
+        self.toasts_draw();
     }
 
 
@@ -311,8 +482,21 @@ impl Game {
             s.set("highscore", &self.high_score.to_string());
             s.set("orangeyellow", &self.unlocks.orangegreen.to_string());
             s.set("purpleyellow", &self.unlocks.purpleyellow.to_string());
+            s.set("palette", &palette_to_string(self.chosen_palette as usize));
+            s.set("custom_palettes", &custom_palettes_to_string(&self.palettes[PALETTE_NAMES.len()..]));
+            s.set("best_wave", &self.best_wave.to_string());
             s.set("sound_volume", &self.music_level.to_string());
             s.set("effect_volume", &self.effect_level.to_string());
+            s.set("colorblind", &self.colorblind.to_string());
+            s.set("reduced_motion", &self.reduced_motion.to_string());
+            s.set("survival_times", &leaderboard_to_string(&self.survival_times));
+            s.set("daily_last_day", &self.daily_last_day.to_string());
+            s.set("daily_best", &daily_best_to_string(&self.daily_best));
+            s.set("highscore_mutators", &mutators_to_string(&self.high_score_mutators));
+            s.set("achievements", &achievements_to_string(&self.achievements));
+            s.set("upgrade_picks", &list_to_string(&self.upgrade_picks));
+            s.set("upgrade_seen", &list_to_string(&self.upgrade_seen));
+            s.set("bestiary", &bestiary_to_string(&self.bestiary));
         }
     }
 
@@ -323,37 +507,22 @@ impl Game {
 
     // =========== ENEMY SPAWN ============
 
+    pub fn spawn_enemy(&mut self, mut enemy: Enemy) {
+        self.enemy_id_counter += 1;
+        enemy.id = self.enemy_id_counter;
+        self.enemies.push(enemy);
+    }
+
     pub fn update_spawning(&mut self, s: &mut SpawnEnemy) {
-        s.spawn_t -= get_frame_time();
+        s.spawn_t -= self.world_dt();
         if s.spawn_t <= 0.0 {
             let mut enemy = s.to_spawn;
             enemy.x = s.x;
             enemy.y = s.y;
-            self.enemies.push(enemy);
+            self.spawn_enemy(enemy);
         }
     }
 
-    pub fn draw_spawning(&mut self,s: &mut SpawnEnemy) {
-        let mut color = match self.color_state {
-            ColorState::Primary => self.palette.fg_primary,
-            ColorState::Secondary => self.palette.fg_secondary
-        };
-
-        color.a = 0.8;
-
-        let spawn_size = 30.0;
-        let spawn_time = 2.0;
-
-        draw_rectangle_lines(s.x, s.y, spawn_size, spawn_size, 6.0, color);
-
-        let curr_size = spawn_size * ((spawn_time - s.spawn_t) / spawn_time);
-        draw_rectangle(
-            s.x + spawn_size/2.0 - curr_size/2.0, 
-            s.y + spawn_size/2.0 - curr_size/2.0, 
-            curr_size, curr_size, color)
-
-    }
-
     // =========== GAME STATE ==============
 
     // pub fn clean_map_and_move_player(&mut self) {
@@ -365,6 +534,7 @@ impl Game {
     pub fn move_player(&mut self) {
         self.bullets = Vec::new();
         self.circle_attacks = Vec::new();
+        self.lasers = Vec::new();
         self.wave.move_player = true;
         self.wave.move_player_t = self.wave.move_player_tmax;
         self.wave.old_x = self.player.x;
@@ -377,33 +547,60 @@ impl Game {
         if is_key_pressed(KeyCode::Key2) { self.debug.debug2 = increment_or_zero(self.debug.debug2, 4); }
         if is_key_pressed(KeyCode::Key3) { self.debug.debug3 = increment_or_zero(self.debug.debug3, 1); }
 
-        if self.wave.current >= 10 {
-            self.unlocks.orangegreen = true;
-            self.should_save = true;
-        }
-
-        if self.wave.current >= 25 {
-            self.unlocks.purpleyellow = true;
-            self.should_save = true;
-        }
-
         if self.player.health <= 0 || is_key_pressed(KeyCode::Escape) {
-            if self.current_score > self.high_score {
+            if self.player.health <= 0 {
+                if let Some(a) = self.last_hit_by {
+                    self.bestiary[a].deaths += 1;
+                    self.should_save = true;
+                }
+            }
+            self.last_hit_by = None;
+            // survival and the daily keep their own boards, the high score is classic runs only
+            let mut new_best = false;
+            if self.mode == GameMode::Classic && self.current_score > self.high_score {
                 self.high_score = self.current_score;
+                self.high_score_mutators = self.rules.mutators.clone();
                 self.should_save = true;
+                new_best = true;
+            }
+            match self.mode {
+                GameMode::Survival => new_best = self.record_survival_time(),
+                GameMode::Daily => new_best = self.record_daily_score(),
+                GameMode::Classic => (),
+            }
+            self.last_run = Some(RunResult {
+                mode: self.mode,
+                score: self.current_score,
+                wave: self.wave.current,
+                time: self.survival.time,
+                mutators: self.rules.mutators.clone(),
+                multiplier: self.rules.score_multiplier(),
+                new_best,
+            });
+            if self.mode == GameMode::Daily {
+                self.end_daily();
             }
 
-            self.player = Player::default();
+            self.player = self.new_player();
             self.wave = Wave::default();
+            self.survival = Survival::default();
+            self.generated_wave = None;
+            self.time_slow_t = 0.0;
+            self.bomb_t = 0.0;
+            self.shake_t = 0.0;
             self.enemies = Vec::new();
             self.bullets = Vec::new();
             self.circle_attacks = Vec::new();
+            self.lasers = Vec::new();
             self.upgrades = Vec::new();
             self.enemy_spawn = Vec::new();
             self.current_score = 0;
+            self.absorb_streak = 0;
+            self.wave_hit = false;
             stop_sound(&self.assets.play_song);
             play_sound(&self.assets.menu_song, PlaySoundParams { looped: true, volume: self.music_level as f32 / 10.0 });
-            self.game_state = GameState::MainMenu;
+            self.game_state = GameState::Score;
+            return;
         }
         
         if self.wave.move_player {
@@ -429,6 +626,7 @@ impl Game {
         }
 
         self.player_update();
+        self.ability_update();
         
         let mut spawners = std::mem::take(&mut self.enemy_spawn);
         spawners.retain_mut(|s| {
@@ -439,59 +637,58 @@ impl Game {
         self.enemy_spawn = spawners;
         
         let mut enemies = std::mem::take(&mut self.enemies);
+        self.enemy_grid.build(&enemies);
         enemies.retain_mut(|e| {
             if e.attack_t >= 0.0 {
-                e.attack_t -= get_frame_time();
+                e.attack_t -= self.world_dt();
             }
             
+            self.update_color_flip(e);
             match e.kind {
                 EnemyType::FollowEnemy => self.update_follow_enemy(e),
                 EnemyType::FollowShootEnemy => self.update_follow_shoot_enemy(e),
-                EnemyType::StaticCircleAttack => self.update_static_circle_enemy(e)
+                EnemyType::StaticCircleAttack => self.update_static_circle_enemy(e),
+                EnemyType::PatternShooter => self.update_pattern_enemy(e),
+                EnemyType::Charger => self.update_charger_enemy(e),
+                EnemyType::Splitter => self.update_splitter_enemy(e),
+                EnemyType::Teleporter => self.update_teleporter_enemy(e),
+                EnemyType::Shielded => self.update_shielded_enemy(e),
+                EnemyType::Sniper => self.update_sniper_enemy(e),
             }
             
             self.enemy_collision(e);
             
             if e.health <= 0.0 {
-                self.current_score += e.score;   
-                play_sound(&self.assets.dead, PlaySoundParams { looped: false, volume: self.effect_level as f32 / 10.0 })
+                self.current_score += (e.score as f32 * self.rules.score_multiplier()).round() as i32;
+                play_sound(&self.assets.dead, PlaySoundParams { looped: false, volume: self.effect_level as f32 / 10.0 });
+                self.achievement_event(GameEvent::EnemyKilled);
+                if let Some(a) = e.archetype {
+                    self.bestiary[a].kills += 1;
+                }
+                for child in e.split_children() {
+                    self.spawn_enemy(child);
+                }
             }
             e.health > 0.0
         });
+        // keep anything spawned during the update, like splitter children
+        enemies.append(&mut self.enemies);
         self.enemies = enemies;
+        self.player_switched = false;
         
 
         let mut circles = std::mem::take(&mut self.circle_attacks);
         circles.retain_mut(|c| {
-            c.radius += get_frame_time() * 500.0 * (c.radius/200.0);
-
-            if !c.hit {
-                // check player distance
-                let diffx = (c.x - self.player.x + self.player.size/2.0).abs();
-                let diffy = (c.y - self.player.y + self.player.size/2.0).abs();
-    
-                // player/circle distance
-                let dist = (diffx * diffx + diffy * diffy).sqrt() - c.radius;
-                
-                // player has size, and circle has thickness
-                let circle_pad = 2.5;
-                let player_pad = self.player.size/2.0;
-                
-                let dist = dist.abs();
-                if dist < circle_pad + player_pad || dist <= circle_pad - player_pad || dist <= player_pad - circle_pad || dist == circle_pad + player_pad {
-                    // inside player
-                    if self.color_state != c.color {
-                        c.hit = true;
-                        play_sound(&self.assets.hit, PlaySoundParams { looped: false, volume: self.effect_level as f32 / 10.0 });
-                        self.player.health -= 2;
-                    } 
-                }
-            }
+            c.radius += self.world_dt() * 500.0 * (c.radius/200.0);
+
+            self.circle_player_coll(c);
 
             c.radius < 2000.0
         });
         self.circle_attacks = circles;
 
+        self.update_lasers();
+
         self.update_upgrades();
         self.upgrades.retain(|_| {
             !self.wave.upgrade_picked
@@ -500,18 +697,23 @@ impl Game {
 
         let mut bullets = std::mem::take(&mut self.bullets);
         bullets.retain_mut(|b| {
-            b.update();
+            self.bullet_homing(b);
+            b.update(self.world_dt());
             self.bullet_collision(b);
 
             !b.hit
         });
+        // keep anything spawned while colliding, like split bullets
+        bullets.append(&mut self.bullets);
         self.bullets = bullets;
 
         if is_key_pressed(KeyCode::C) {
-            self.palette = self.palettes[ rand::gen_range(0, self.palettes.len()) ]
+            // only ones you could pick from the menu anyway
+            let unlocked: Vec<usize> = (0..self.palettes.len()).filter(|i| self.palette_unlocked(*i)).collect();
+            self.palette = self.palettes[ unlocked[rand::gen_range(0, unlocked.len())] ]
         }
 
-        if self.wave.state != WaveState::Start && is_key_pressed(KeyCode::Space){
+        if self.wave.state != WaveState::Start && !self.rules.has(Mutator::NoSwitch) && is_key_pressed(KeyCode::Space){
             if self.wave.current == 0 {
                 self.switch_effect_total = 0.3;
             }
@@ -520,9 +722,7 @@ impl Game {
         }
         
         if is_key_pressed(KeyCode::B) {
-            self.enemies.push( 
-                self.enemy_list[1]
-            );
+            self.spawn_enemy(self.enemy_list[1]);
         }
 
         
@@ -531,10 +731,25 @@ impl Game {
         }
         if self.switch_effect_t <= 0.0 && self.switch_effect_t > -1.0 {
             self.color_state = self.color_state.next();
+            self.player_switched = true;
             self.switch_effect_t = -2.0;
             self.switch_effect_total = 0.0;
         }
 
+        // the reduced motion background heads for the color being switched to, at a capped speed
+        // so spamming the switch can't flash the whole screen
+        let next = if self.switch_effect_t > 0.0 { self.color_state.next() } else { self.color_state };
+        let target = if next == ColorState::Secondary { 1.0 } else { 0.0 };
+        let step = get_frame_time() / REDUCED_MOTION_FADE_TIME;
+        self.bg_blend += (target - self.bg_blend).clamp(-step, step);
+
+        match self.mode {
+            GameMode::Classic | GameMode::Daily => self.wave_update(),
+            GameMode::Survival => self.survival_update(),
+        }
+    }
+
+    pub fn wave_update(&mut self) {
         match self.wave.state {
             WaveState::Start => {
                 if self.wave.current != 0 && !self.wave.start_spawned {
@@ -556,50 +771,24 @@ impl Game {
 
                 // Wave started, everyting got defeated
                 if !self.wave.enemies_set {
-                    let enemies_to_spawn = 20 + self.wave.current * 3;
-                    self.wave.enemy_remaining = enemies_to_spawn;
-                    self.wave.spawn_delay_tmax = 5.0 - self.wave.current as f32 * 0.05;
-                    self.wave.enemies_set = true;
-                }
-
-                self.wave.spawn_delay_t -= get_frame_time();
-                if self.wave.spawn_delay_t > 0.0 && self.enemies.len() == 0 && self.enemy_spawn.len() == 0 {
-                    self.wave.spawn_delay_t = 0.0;
-                }
-
-                if self.wave.spawn_delay_t <= 0.0 {
-                    if self.wave.enemies_set && self.wave.enemy_remaining > 0 {
-                        let to_spawn = min(5, self.wave.enemy_remaining);
-
-
-                        let rad_x = rand::gen_range(50.0, DESIGN_WIDTH - 400.0);
-                        let rad_y = rand::gen_range(50.0,  DESIGN_HEIGHT - 350.0);
-
-                        for _ in 0..to_spawn {
-                            let x = rand::gen_range(0.0, 300.0);
-                            let y = rand::gen_range(0.0, 300.0);
-                            self.enemy_spawn.push(
-                                SpawnEnemy {
-                                    x: rad_x + x, y: rad_y + y,
-                                    spawn_t: 2.0,
-                                    to_spawn: self.enemy_list[ rand::gen_range(0, self.enemy_list.len())],
-                                }
-                            );
-        
-                            self.wave.enemy_remaining -= 1;
-                        }
-                        self.wave.spawn_delay_t = self.wave.spawn_delay_tmax;
+                    self.wave_runner = ScriptRunner::new();
+                    if self.wave.current as usize > self.waves.len() {
+                        // past the last scripted wave
+                        self.generate_next_wave();
                     }
+                    self.wave.enemy_remaining = self.scripted_wave().map_or(0, |w| w.enemy_count());
+                    self.wave.enemies_set = true;
                 }
 
-
+                self.update_wave_script();
 
                 if self.wave.enemy_remaining == 0 && self.enemy_spawn.len() == 0 && self.enemies.len() == 0 {
                     self.wave.state = WaveState::Start;
+                    self.achievement_event(GameEvent::WaveCleared(self.wave.current));
                     self.wave.current += 1;
+                    self.achievement_event(GameEvent::WaveReached(self.wave.current));
                     self.wave.upgrade_picked = false;
                     self.wave.enemies_set = false;
-                    self.wave.spawn_delay_t = 0.0;
                 }
                 
             }
@@ -626,14 +815,19 @@ impl Game {
         };
 
 
-        clear_background(bg_color);
+        if self.reduced_motion {
+            let faded = self.palette.bg_primary.to_vec().lerp(self.palette.bg_secondary.to_vec(), self.bg_blend);
+            clear_background(Color::from_vec(faded));
+        } else {
+            clear_background(bg_color);
+        }
 
         if self.wave.current == 0 {
             draw_texture(&self.assets.controls, 0.0, 0.0, color);
         }
 
         // draw switch effect before everything else
-        if self.switch_effect_t > 0.0 {
+        if self.switch_effect_t > 0.0 && !self.reduced_motion {
             draw_circle(self.player.x, self.player.y, 
                 2000.0 * (self.switch_effect_total - self.switch_effect_t) / self.switch_effect_total,
                 bg_color_invert);
@@ -644,7 +838,18 @@ impl Game {
             match e.kind {
                 EnemyType::FollowEnemy => self.draw_follow_enemy(e),
                 EnemyType::FollowShootEnemy => self.draw_follow_shoot_enemy(e),
-                EnemyType::StaticCircleAttack => self.draw_static_circle_enemy(e)
+                EnemyType::StaticCircleAttack => self.draw_static_circle_enemy(e),
+                EnemyType::PatternShooter => self.draw_pattern_enemy(e),
+                EnemyType::Charger => self.draw_charger_enemy(e),
+                EnemyType::Splitter => self.draw_splitter_enemy(e),
+                EnemyType::Teleporter => self.draw_teleporter_enemy(e),
+                EnemyType::Shielded => self.draw_shielded_enemy(e),
+                EnemyType::Sniper => self.draw_sniper_enemy(e),
+            }
+            self.draw_color_flip(e);
+            if self.colorblind {
+                let center = e.center();
+                draw_state_glyph(e.state, center.x, center.y, e.size * 0.5, BLACK);
             }
         }
         self.enemies = enemies;
@@ -655,10 +860,21 @@ impl Game {
                 ColorState::Primary => self.palette.fg_primary,
                 ColorState::Secondary => self.palette.fg_secondary
             };
-            draw_circle_lines(c.x, c.y, c.radius, 5.0, color)
+            if self.colorblind && c.color == ColorState::Secondary {
+                // dashed ring for secondary
+                for i in 0..16 {
+                    draw_arc(c.x, c.y, 8, c.radius, i as f32 * 22.5, 5.0, 12.0, color);
+                }
+            } else {
+                draw_circle_lines(c.x, c.y, c.radius, 5.0, color)
+            }
         }
         self.circle_attacks = circles;
         
+        for l in self.lasers.iter() {
+            self.laser_draw(l);
+        }
+
         let mut bullets = std::mem::take(&mut self.bullets);
         for b in bullets.iter_mut() {
             self.bullet_draw(&b);
@@ -672,13 +888,21 @@ impl Game {
         self.enemy_spawn = spawners;
 
         self.draw_upgrades();
+        self.ability_draw();
         self.player_draw();
         
         let x_center = DESIGN_WIDTH / 2.0;
-        let wave_txt = format!("Wave {}", self.wave.current);
+        let wave_txt = match self.mode {
+            GameMode::Classic | GameMode::Daily => format!("Wave {}", self.wave.current),
+            GameMode::Survival => format_time(self.survival.time),
+        };
         draw_text_centered(&wave_txt, x_center, 50.0, 20.0, &self.assets.font_monogram);
         let score = format!("score: {}", self.current_score);
         draw_text_centered(&score, x_center, 110.0, 8.0, &self.assets.font_monogram);
+        if self.mode == GameMode::Survival {
+            let next = format!("next upgrade: {}", self.survival.next_upgrade_score);
+            draw_text_centered(&next, x_center, 140.0, 6.0, &self.assets.font_monogram);
+        }
     }
 }
 
@@ -736,6 +960,19 @@ pub fn rect_collide(r1: Rect, r2: Rect) -> bool {
     && r1.y + r1.h > r2.y;
 }
 
+// Comma separated save data for per-entry stats
+pub fn list_to_string<T: ToString>(list: &[T]) -> String {
+    list.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(",")
+}
+
+// Missing or broken entries keep their default, so a save from before a list grew still loads
+pub fn parse_list<T: std::str::FromStr + Copy>(s: &str, default: &[T]) -> Vec<T> {
+    let saved: Vec<&str> = s.split(',').collect();
+    default.iter().enumerate()
+        .map(|(i, d)| saved.get(i).and_then(|v| v.parse().ok()).unwrap_or(*d))
+        .collect()
+}
+
 pub fn increment_or_zero(num: i32, max: i32) -> i32 {
     if num + 1 > max { 0 } else { num + 1}
 }
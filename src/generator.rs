@@ -0,0 +1,222 @@
+use crate::game::*;
+use crate::spawn::*;
+use crate::script::*;
+
+// Threat points a wave gets to spend, base + linear + a slow curve on top
+pub struct BudgetCurve {
+    pub base: f32,
+    pub per_wave: f32,
+    pub growth: f32,
+    pub exponent: f32,
+}
+
+pub const BUDGET_CURVE: BudgetCurve = BudgetCurve { base: 20.0, per_wave: 4.0, growth: 0.6, exponent: 1.4 };
+
+impl BudgetCurve {
+    pub fn budget(&self, wave: i32) -> f32 {
+        let w = wave as f32;
+        self.base + self.per_wave * w + self.growth * w.powf(self.exponent)
+    }
+}
+
+// name, cost, first wave it's bought on, largest group of it
+pub const ARCHETYPE_COSTS: [(&str, i32, i32, i32); 11] = [
+    ("swarm_primary", 1, 1, 12),
+    ("swarm_secondary", 1, 1, 12),
+    ("flicker", 2, 4, 10),
+    ("mimic", 2, 5, 10),
+    ("shooter", 4, 2, 5),
+    ("charger", 4, 4, 5),
+    ("splitter", 5, 5, 4),
+    ("teleporter", 5, 6, 4),
+    ("shielded", 6, 6, 4),
+    ("tower", 8, 3, 2),
+    ("sniper", 9, 7, 2),
+];
+
+// bounds on the time between groups so late waves don't flood the screen
+pub const MIN_GROUP_INTERVAL: f32 = 1.5;
+pub const MAX_GROUP_INTERVAL: f32 = 4.0;
+// no single archetype gets more than this share of the budget
+pub const MAX_ARCHETYPE_SHARE: f32 = 0.4;
+
+#[derive(Clone)]
+pub struct ShopItem {
+    pub name: String,
+    pub cost: i32,
+    pub min_wave: i32,
+    pub max_group: i32,
+}
+
+pub fn difficulty_multiplier(difficulty: i32) -> f32 {
+    match difficulty {
+        0 => 0.75,
+        d => 1.0 + 0.25 * (d - 1) as f32,
+    }
+}
+
+pub fn group_interval(wave: i32) -> f32 {
+    (MAX_GROUP_INTERVAL - wave as f32 * 0.05).clamp(MIN_GROUP_INTERVAL, MAX_GROUP_INTERVAL)
+}
+
+// Buys groups until the budget is gone, roll gives numbers in [0, n) so this stays deterministic for a seed
+pub fn generate_wave(shop: &[ShopItem], wave: i32, budget: f32, roll: &mut dyn FnMut(usize) -> usize) -> ScriptedWave {
+    let available: Vec<&ShopItem> = shop.iter().filter(|s| s.min_wave <= wave && s.cost > 0).collect();
+    let mut groups: Vec<SpawnGroup> = Vec::new();
+    let mut spent = vec![0.0; available.len()];
+    let mut left = budget;
+    let mut last: Option<usize> = None;
+
+    loop {
+        let affordable: Vec<usize> = (0..available.len())
+            .filter(|&i| available[i].cost as f32 <= left)
+            .filter(|&i| spent[i] + available[i].cost as f32 <= budget * MAX_ARCHETYPE_SHARE || available.len() == 1)
+            .filter(|&i| Some(i) != last || available.len() == 1)
+            .collect();
+        if affordable.is_empty() {
+            break;
+        }
+
+        let i = affordable[roll(affordable.len())];
+        let item = available[i];
+        let share_left = budget * MAX_ARCHETYPE_SHARE - spent[i];
+        let max_count = (left.min(share_left.max(item.cost as f32)) / item.cost as f32) as i32;
+        let count = 1 + roll(item.max_group.min(max_count).max(1) as usize) as i32;
+
+        let formation = match roll(4) {
+            0 => Formation::Ring,
+            1 => Formation::Line,
+            2 => Formation::Edge,
+            _ => Formation::Cluster,
+        };
+        // every few groups wait for the screen to thin out instead of a timer
+        let trigger = match groups.len() {
+            0 => Trigger::Time(0.0),
+            n if n % 4 == 0 => Trigger::Health(0.4),
+            _ => Trigger::Time(group_interval(wave)),
+        };
+
+        groups.push(SpawnGroup { archetype: item.name.clone(), count, formation, delay: 0.0, trigger });
+        spent[i] += (count * item.cost) as f32;
+        left -= (count * item.cost) as f32;
+        last = Some(i);
+    }
+
+    if groups.is_empty() {
+        groups.push(SpawnGroup {
+            archetype: String::from("swarm_primary"),
+            count: 1,
+            formation: Formation::Cluster,
+            delay: 0.0,
+            trigger: Trigger::Time(0.0),
+        });
+    }
+    ScriptedWave { groups }
+}
+
+pub fn archetype_shop() -> Vec<ShopItem> {
+    ARCHETYPE_COSTS.iter()
+        .map(|(name, cost, min_wave, max_group)| ShopItem {
+            name: name.to_string(), cost: *cost, min_wave: *min_wave, max_group: *max_group,
+        })
+        .collect()
+}
+
+impl Game {
+    // built in archetypes plus the pattern enemies, priced off their score
+    pub fn wave_shop(&self) -> Vec<ShopItem> {
+        let mut shop = archetype_shop();
+
+        for p in self.pattern_enemies.iter() {
+            shop.push(ShopItem {
+                name: p.name.clone(),
+                cost: (p.enemy.score / 5).max(1),
+                min_wave: p.min_wave,
+                max_group: 2,
+            });
+        }
+        shop
+    }
+
+    pub fn generate_next_wave(&mut self) {
+        let budget = BUDGET_CURVE.budget(self.wave.current) * difficulty_multiplier(self.difficulty_select);
+        let shop = self.wave_shop();
+        let rng = &self.run_rng;
+        self.generated_wave = Some(generate_wave(&shop, self.wave.current, budget, &mut |n| rng.below(n)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a fixed sequence so every run generates the same waves
+    fn pinned_roll(seed: u64) -> impl FnMut(usize) -> usize {
+        let mut state = seed;
+        move |n| {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (state >> 33) as usize % n
+        }
+    }
+
+    fn wave_cost(shop: &[ShopItem], wave: &ScriptedWave) -> i32 {
+        wave.groups.iter()
+            .map(|g| g.count * shop.iter().find(|s| s.name == g.archetype).unwrap().cost)
+            .sum()
+    }
+
+    #[test]
+    fn stays_within_budget() {
+        let shop = archetype_shop();
+        for seed in 0..20 {
+            let mut roll = pinned_roll(seed);
+            for wave in 1..=40 {
+                let budget = BUDGET_CURVE.budget(wave);
+                let w = generate_wave(&shop, wave, budget, &mut roll);
+                assert!(!w.groups.is_empty());
+                assert!(wave_cost(&shop, &w) as f32 <= budget, "seed {} wave {} over budget", seed, wave);
+            }
+        }
+    }
+
+    #[test]
+    fn same_roll_same_wave() {
+        let shop = archetype_shop();
+        let a = generate_wave(&shop, 10, BUDGET_CURVE.budget(10), &mut pinned_roll(7));
+        let b = generate_wave(&shop, 10, BUDGET_CURVE.budget(10), &mut pinned_roll(7));
+        let names = |w: &ScriptedWave| w.groups.iter().map(|g| (g.archetype.clone(), g.count)).collect::<Vec<_>>();
+        assert_eq!(names(&a), names(&b));
+    }
+
+    #[test]
+    fn archetypes_wait_for_their_wave() {
+        let shop = archetype_shop();
+        for seed in 0..20 {
+            let mut roll = pinned_roll(seed);
+            for wave in 1..=10 {
+                let w = generate_wave(&shop, wave, BUDGET_CURVE.budget(wave), &mut roll);
+                for g in w.groups.iter() {
+                    let item = shop.iter().find(|s| s.name == g.archetype).unwrap();
+                    assert!(item.min_wave <= wave, "{} offered on wave {}", g.archetype, wave);
+                }
+            }
+        }
+
+        // always picking the last affordable option still can't reach past the unlocks
+        let w = generate_wave(&shop, 1, BUDGET_CURVE.budget(1), &mut |n| n - 1);
+        assert!(w.groups.iter().all(|g| g.archetype.starts_with("swarm_")));
+    }
+
+    #[test]
+    fn unlocked_archetypes_show_up() {
+        let shop = archetype_shop();
+        let mut roll = pinned_roll(1);
+        let seen: Vec<String> = (0..50)
+            .flat_map(|_| generate_wave(&shop, 7, BUDGET_CURVE.budget(7), &mut roll).groups)
+            .map(|g| g.archetype)
+            .collect();
+        for (name, _, _, _) in ARCHETYPE_COSTS {
+            assert!(seen.iter().any(|s| s == name), "{} never picked by wave 7", name);
+        }
+    }
+}
@@ -45,5 +45,14 @@ impl Game {
         draw_texture_ex(texture, offset, y, color, 
             DrawTextureParams { dest_size: Some(Vec2 { x: self.player.size * scale, y: self.player.size * scale }), ..Default::default()});
 
+        // ability sits right after the last hp bar
+        let ability_size = 50.0;
+        let ability_x = offset + (self.player.size * scale) + 30.0 + (self.player.max_health as f32 * (width + gap)) + 30.0;
+        let mut ability_y = DESIGN_HEIGHT - offset - ability_size - 30.0;
+        if self.debug.debug1 == 1 {
+            ability_y = 50.0;
+        }
+        self.ability_hud_draw(ability_x, ability_y, ability_size, color);
+
     }
 }
\ No newline at end of file
@@ -0,0 +1,148 @@
+use macroquad::prelude::*;
+use crate::game::*;
+use crate::bullet::*;
+use crate::colors::*;
+use crate::collision::Shape;
+
+// A beam that warns first, then hurts anything in the line for as long as it's on
+pub struct Laser {
+    pub x: f32,
+    pub y: f32,
+    pub dir: Vec2,
+    pub length: f32,
+    pub width: f32,
+    pub state: ColorState,
+    pub kind: BulletType,
+    pub damage: f32,
+    pub charge_t: f32,    // telegraph time left, no damage yet
+    pub charge_tmax: f32,
+    pub fire_t: f32,      // how long the beam stays on after charging
+    pub tick_t: f32,      // time until the next damage tick
+    pub tick_rate: f32,
+    pub source: Option<usize>,
+}
+
+impl Laser {
+    pub fn new(x: f32, y: f32, dir: Vec2, width: f32, charge: f32, fire: f32, kind: BulletType) -> Laser {
+        Laser {
+            x, y, dir, width, kind,
+            length: 2000.0,
+            state: ColorState::Primary,
+            damage: 1.0,
+            charge_t: charge,
+            charge_tmax: charge,
+            fire_t: fire,
+            tick_t: 0.0,
+            tick_rate: 0.25,
+            source: None,
+        }
+    }
+
+    pub fn firing(&self) -> bool {
+        self.charge_t <= 0.0 && self.fire_t > 0.0
+    }
+
+    pub fn done(&self) -> bool {
+        self.charge_t <= 0.0 && self.fire_t <= 0.0
+    }
+
+    pub fn end(&self) -> Vec2 {
+        Vec2 { x: self.x, y: self.y } + self.dir * self.length
+    }
+
+    pub fn get_shape(&self) -> Shape {
+        Shape::Capsule { a: Vec2 { x: self.x, y: self.y }, b: self.end(), r: self.width / 2.0 }
+    }
+
+    // true on the frames the beam should deal damage
+    pub fn update(&mut self, dt: f32) -> bool {
+        if self.charge_t > 0.0 {
+            self.charge_t -= dt;
+            return false;
+        }
+
+        self.fire_t -= dt;
+        self.tick_t -= dt;
+        if self.tick_t <= 0.0 {
+            self.tick_t = self.tick_rate;
+            return true;
+        }
+        false
+    }
+}
+
+impl Game {
+    pub fn laser_collision(&mut self, l: &mut Laser) {
+        let shape = l.get_shape();
+        match l.kind {
+            BulletType::Player => {
+                let mut enemies = std::mem::take(&mut self.enemies);
+                for e in enemies.iter_mut() {
+                    // shields face the beam's origin the same way they face a bullet's
+                    if shape.overlaps(&e.get_shape()) && !e.blocks(Vec2 { x: l.x, y: l.y }) {
+                        e.health -= l.damage * self.damage_multiplier(l.state, e);
+                    }
+                }
+                self.enemies = enemies;
+            }
+            BulletType::Enemy => {
+                if !shape.overlaps(&self.player.get_shape()) {
+                    return;
+                }
+
+                // matching color passes through harmlessly
+                if l.state != self.color_state {
+                    self.damage_player(l.damage as i32, l.source);
+                }
+            }
+        }
+    }
+
+    pub fn update_lasers(&mut self) {
+        let mut lasers = std::mem::take(&mut self.lasers);
+        lasers.retain_mut(|l| {
+            if l.update(self.world_dt()) {
+                self.laser_collision(l);
+            }
+            !l.done()
+        });
+        lasers.append(&mut self.lasers);
+        self.lasers = lasers;
+    }
+
+    pub fn laser_draw(&self, l: &Laser) {
+        let mut color = match l.state {
+            ColorState::Primary => self.palette.fg_primary,
+            ColorState::Secondary => self.palette.fg_secondary,
+        };
+        if l.kind == BulletType::Player && !self.rules.polarity {
+            color = WHITE;
+        }
+        let end = l.end();
+
+        if !l.firing() {
+            // thin sight line that gets more solid as it charges
+            let t = 1.0 - l.charge_t / l.charge_tmax;
+            color.a = 0.2 + 0.5 * t;
+            draw_line(l.x, l.y, end.x, end.y, 2.0, color);
+            return;
+        }
+
+        // color blind mode gives secondary beams a dark core
+        let core = if self.colorblind && l.state == ColorState::Secondary { BLACK } else { WHITE };
+        draw_line(l.x, l.y, end.x, end.y, l.width, color);
+        draw_line(l.x, l.y, end.x, end.y, l.width * 0.3, core);
+    }
+
+    pub fn player_fire_laser(&mut self, dir: Vec2) {
+        let center = Vec2 { x: self.player.x + self.player.size / 2.0, y: self.player.y + self.player.size / 2.0 };
+        let level = self.player.laser_level as f32;
+
+        let mut l = Laser::new(center.x, center.y, dir, 6.0 + 4.0 * level, 0.0, 0.15, BulletType::Player);
+        l.damage = self.player.damage * (1.0 + 0.5 * level);
+        l.state = self.color_state;
+        // one tick per shot, the beam only stays up to be seen
+        l.tick_rate = 1.0;
+        self.lasers.push(l);
+    }
+}
@@ -30,6 +30,22 @@ mod assets;
 mod options;
 mod wave;
 mod menu;
+mod pattern;
+mod rules;
+mod steering;
+mod laser;
+mod ability;
+mod spawn;
+mod script;
+mod generator;
+mod survival;
+mod daily;
+mod mutators;
+mod results;
+mod achievements;
+mod bestiary;
+mod credits;
+mod palette_editor;
 
 use miniquad::window::screen_size;
 
@@ -42,6 +58,9 @@ async fn main() {
     let storage = &mut quad_storage::STORAGE.lock().unwrap();    
 
     
+    // every save has a high score, so without one this is the first launch
+    let first_launch = storage.get("highscore").is_none();
+
     let highscore = storage.get("highscore");
     if highscore.is_none() {
         storage.set("highscore", &0.to_string());
@@ -52,6 +71,12 @@ async fn main() {
 
     
 
+    if let Some(highscore_mutators) = storage.get("highscore_mutators") {
+        game.high_score_mutators = rules::parse_mutators(&highscore_mutators);
+    } else {
+        storage.set("highscore_mutators", "");
+    }
+
     let sound = storage.get("sound_volume");
     if sound.is_none() {
         storage.set("sound_volume", &3.to_string());
@@ -60,6 +85,21 @@ async fn main() {
         game.music_level = sound.parse::<i32>().unwrap();
     }
 
+    // ask new players about motion before anything flashes, older saves just get it off
+    if let Some(reduced_motion) = storage.get("reduced_motion") {
+        game.reduced_motion = reduced_motion.parse::<bool>().unwrap_or(false);
+    } else if first_launch {
+        game.game_state = GameState::FirstLaunch;
+    } else {
+        storage.set("reduced_motion", &false.to_string());
+    }
+
+    if let Some(colorblind) = storage.get("colorblind") {
+        game.colorblind = colorblind.parse::<bool>().unwrap_or(false);
+    } else {
+        storage.set("colorblind", &false.to_string());
+    }
+
     let effect = storage.get("effect_volume");
     if effect.is_none() {
         storage.set("effect_volume", &3.to_string());
@@ -70,6 +110,48 @@ async fn main() {
 
 
 
+    if let Some(survival_times) = storage.get("survival_times") {
+        game.survival_times = survival::parse_leaderboard(&survival_times);
+    } else {
+        storage.set("survival_times", "");
+    }
+
+    if let Some(daily_last_day) = storage.get("daily_last_day") {
+        game.daily_last_day = daily_last_day.parse::<i64>().unwrap_or(-1);
+    } else {
+        storage.set("daily_last_day", &(-1).to_string());
+    }
+
+    if let Some(daily_best) = storage.get("daily_best") {
+        game.daily_best = daily::parse_daily_best(&daily_best);
+    } else {
+        storage.set("daily_best", "");
+    }
+
+    if let Some(achievements) = storage.get("achievements") {
+        game.achievements = achievements::parse_achievements(&achievements);
+    } else {
+        storage.set("achievements", "");
+    }
+
+    if let Some(upgrade_picks) = storage.get("upgrade_picks") {
+        game.upgrade_picks = parse_list(&upgrade_picks, &game.upgrade_picks);
+    } else {
+        storage.set("upgrade_picks", &list_to_string(&game.upgrade_picks));
+    }
+
+    if let Some(upgrade_seen) = storage.get("upgrade_seen") {
+        game.upgrade_seen = parse_list(&upgrade_seen, &game.upgrade_seen);
+    } else {
+        storage.set("upgrade_seen", &list_to_string(&game.upgrade_seen));
+    }
+
+    if let Some(bestiary) = storage.get("bestiary") {
+        bestiary::parse_bestiary(&bestiary, &mut game.bestiary);
+    } else {
+        storage.set("bestiary", &bestiary::bestiary_to_string(&game.bestiary));
+    }
+
     let orangeyellow = storage.get("orangeyellow");
     if orangeyellow.is_none() {
         storage.set("orangeyellow", &false.to_string());
@@ -85,6 +167,30 @@ async fn main() {
         let purpleyellow = purpleyellow.unwrap();
         game.unlocks.purpleyellow = purpleyellow.parse::<bool>().unwrap();
     }
+
+    if let Some(best_wave) = storage.get("best_wave") {
+        game.best_wave = best_wave.parse::<i32>().unwrap_or(0);
+    } else {
+        storage.set("best_wave", &0.to_string());
+    }
+
+    if let Some(custom_palettes) = storage.get("custom_palettes") {
+        game.palettes.append(&mut colors::parse_custom_palettes(&custom_palettes));
+    } else {
+        storage.set("custom_palettes", "");
+    }
+
+    // after the unlocks and custom palettes, a palette that's somehow locked or gone falls back to the default
+    if let Some(palette) = storage.get("palette") {
+        let palette = colors::parse_palette(&palette, game.palettes.len()).unwrap_or(0);
+        if game.palette_unlocked(palette) {
+            game.chosen_palette = palette as i32;
+            game.curr_palette_idx = palette as i32;
+            game.palette = game.palettes[palette];
+        }
+    } else {
+        storage.set("palette", &colors::palette_to_string(0));
+    }
     macroquad::rand::srand(time as u64);
     let canvas = render_target(DESIGN_WIDTH as u32, DESIGN_HEIGHT as u32);
     canvas.texture.set_filter(FilterMode::Nearest);
@@ -114,7 +220,8 @@ async fn main() {
 
         set_default_camera();
         clear_background(BLANK);
-        draw_texture_ex(&canvas.texture, x_center, y_center, WHITE, 
+        let shake = game.screen_shake() * scale;
+        draw_texture_ex(&canvas.texture, x_center + shake.x, y_center + shake.y, WHITE, 
             DrawTextureParams {
                 dest_size: Some( Vec2 { x: DESIGN_WIDTH * scale, y: DESIGN_HEIGHT * scale  }),
                 ..Default::default()
@@ -5,6 +5,10 @@ use macroquad::prelude::*;
 use macroquad::audio::*;
 
 use crate::game::*;
+use crate::survival::*;
+use crate::rules::*;
+use crate::colors::*;
+use crate::achievements::*;
 
 impl Game {
 
@@ -60,12 +64,19 @@ impl Game {
         }
 
         if down {
-            self.menu_selected = min(4, self.menu_selected + 1);
+            self.menu_selected = min(7, self.menu_selected + 1);
             self.menu_switch();
         }
 
-        if self.menu_selected == 0 {
+        // a locked palette was only being previewed
+        if (up || down) && self.menu_selected != 4 {
+            self.curr_palette_idx = self.chosen_palette;
+        }
+
+        if self.menu_selected == 0 || self.menu_selected == 1 {
             if interact {
+                self.mode = if self.menu_selected == 0 { GameMode::Classic } else { GameMode::Survival };
+                self.menu_selected = 0;
                 self.game_state = GameState::Characters;
                 // stop_sound(&self.assets.menu_song);
                 // play_sound(&self.assets.play_song, PlaySoundParams { looped: true, volume: self.music_level as f32 / 10.0});
@@ -73,7 +84,13 @@ impl Game {
             }
         }
 
-        if self.menu_selected == 1 {
+        if self.menu_selected == 2 {
+            if interact {
+                self.start_daily();
+            }
+        }
+
+        if self.menu_selected == 3 {
             if interact {
                 self.game_state = GameState::Options;
                 self.menu_selected = 0;
@@ -81,7 +98,7 @@ impl Game {
             }
         }
         
-        if self.menu_selected == 2 {
+        if self.menu_selected == 4 {
             if left { 
                 self.curr_palette_idx -= 1;
                 if self.curr_palette_idx < 0 { 
@@ -98,20 +115,41 @@ impl Game {
                 self.menu_switch();
             }
             
-            self.palette = match self.curr_palette_idx {
-                1 => if true { self.palettes[1] } else { self.palettes[0] }
-                2 => if true { self.palettes[2] } else { self.palettes[0] }
-                _ => self.palettes[0],
+            if interact {
+                self.open_palette_editor();
+                return;
+            }
+
+            if self.curr_palette_idx != self.chosen_palette && self.palette_unlocked(self.curr_palette_idx as usize) {
+                self.chosen_palette = self.curr_palette_idx;
+                self.palette = self.palettes[self.chosen_palette as usize];
+                self.should_save = true;
             }
         }
 
-        if self.menu_selected == 3 {
+        if self.menu_selected == 5 {
             if interact {
                 self.game_state = GameState::Collection;
                 self.menu_selected = 0;
                 self.menu_switch();
             }
         }
+
+        if self.menu_selected == 6 {
+            if interact {
+                self.game_state = GameState::Achievements;
+                self.collection_x = 0;
+                self.menu_switch();
+            }
+        }
+
+        if self.menu_selected == 7 {
+            if interact {
+                self.game_state = GameState::Credits;
+                self.credits_scroll = -DESIGN_HEIGHT;
+                self.menu_switch();
+            }
+        }
     }
 
     pub fn help_text(&mut self) {
@@ -133,14 +171,20 @@ impl Game {
         draw_texture(&self.assets.menu2, x_center - 80.0, 100.0, self.palette.fg_secondary);
         draw_text_centered(" COLOR  SWITCH ", x_center, 120.0, 30.0, &self.assets.font_monogram);
         draw_text_centered(&format!("Highscore: {} ", self.high_score), x_center, 220.0, 15.0, &self.assets.font_monogram);
+        if !self.high_score_mutators.is_empty() {
+            draw_text_centered_c(&mutators_name(&self.high_score_mutators), x_center, 260.0, 6.0, &self.assets.font_monogram, GRAY);
+        }
         self.help_text();
 
 
         let menu_txt = vec![
             String::from("Play"),
+            String::from("Survival"),
+            String::from("Daily challenge"),
             String::from("Options"),
             String::from("Color palette"),
             String::from("Collection"),
+            String::from("Achievements"),
             String::from("Credits"),
         ];
         // let menu_txt = vec![
@@ -151,19 +195,79 @@ impl Game {
 
         for i in 0..menu_txt.len() {
             let mut text: String;
-            if self.menu_selected == 2 && i == 2 {
-                text = match self.curr_palette_idx {
-                    0 => "Red & Blue".to_string(),
-                    1 => if true { "Orange & Green".to_string() } else { "Reach wave 10 to unlock".to_string() }
-                    2 => if true { "Purple & Yellow".to_string() } else { "Reach wave 25 to unlock".to_string() }
-                    _ => "what".to_string()
-                };
+            if self.menu_selected == 4 && i == 4 {
+                let idx = self.curr_palette_idx as usize;
+                text = if self.palette_unlocked(idx) { self.palette_name(idx) } else { format!("{} (locked)", self.palette_name(idx)) };
                 text = format!("> {} <", text);
             } else {
                 text = if i == self.menu_selected as usize { format!("> {} <", menu_txt[i]) } else { menu_txt[i].to_string() };
             }
-            draw_text_centered(&text, x_center, 540.0 + (i as f32 * 60.0), font_size, &self.assets.font_monogram);
+            draw_text_centered(&text, x_center, 430.0 + (i as f32 * 52.0), font_size, &self.assets.font_monogram);
+
+        }
+
+        if self.menu_selected == 1 {
+            self.survival_leaderboard_draw(x_center + 450.0, 540.0);
+        }
+        if self.menu_selected == 2 {
+            self.daily_draw(x_center + 450.0, 420.0);
+        }
+        if self.menu_selected == 4 {
+            self.palette_preview_draw(self.curr_palette_idx as usize, x_center + 450.0, 560.0);
+        }
+    }
+
+    pub fn palette_unlocked(&self, idx: usize) -> bool {
+        match idx {
+            1 => self.unlocks.orangegreen,
+            2 => self.unlocks.purpleyellow,
+            _ => true,
+        }
+    }
+
+    pub fn palette_name(&self, idx: usize) -> String {
+        match PALETTE_NAMES.get(idx) {
+            Some(name) => name.to_string(),
+            None => format!("Custom {}", idx - PALETTE_NAMES.len() + 1),
+        }
+    }
+
+    pub fn unlock_palette(&mut self, idx: usize) {
+        if self.palette_unlocked(idx) {
+            return;
+        }
+        match idx {
+            1 => self.unlocks.orangegreen = true,
+            2 => self.unlocks.purpleyellow = true,
+            _ => return,
+        }
+        self.should_save = true;
+        self.toasts.push(Toast { text: format!("Palette unlocked: {}", self.palette_name(idx)), t: TOAST_TIME });
+    }
+
+    // what it takes to unlock a palette, from the achievement that gives it
+    pub fn palette_hint(&self, idx: usize) -> String {
+        let Some(a) = ACHIEVEMENTS.iter().find(|a| a.reward == Some(Reward::Palette(idx))) else {
+            return String::from("Locked");
+        };
+        match a.goal {
+            Goal::Wave { wave, difficulty: None } => format!("Reach wave {} to unlock (best {})", wave, self.best_wave),
+            _ => format!("{} to unlock", a.description),
+        }
+    }
+
+    // the palette's colors on its own backgrounds, dimmed with a hint when locked
+    pub fn palette_preview_draw(&self, idx: usize, x: f32, y: f32) {
+        let p = self.palettes[idx];
+        let locked = !self.palette_unlocked(idx);
+        self.palette_mock_draw(&p, x - 150.0, y, 300.0, 150.0, if locked { 0.3 } else { 1.0 });
 
+        if locked {
+            draw_text_centered("Locked", x, y + 85.0, 10.0, &self.assets.font_monogram);
+            draw_text_centered_c(&self.palette_hint(idx), x, y + 190.0, 6.0, &self.assets.font_monogram, GRAY);
+        } else {
+            let hint = if idx >= PALETTE_NAMES.len() { "Enter - edit" } else { "Enter - new custom palette" };
+            draw_text_centered_c(hint, x, y + 190.0, 6.0, &self.assets.font_monogram, GRAY);
         }
     }
 
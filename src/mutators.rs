@@ -0,0 +1,68 @@
+use std::cmp::{max, min};
+
+use macroquad::prelude::*;
+
+use crate::game::*;
+use crate::rules::*;
+
+impl Game {
+    pub fn mutators_update(&mut self) {
+        self.background_update();
+        let interact = is_key_pressed(KeyCode::Space) || is_key_pressed(KeyCode::Enter);
+        let up = is_key_pressed(KeyCode::W) || is_key_pressed(KeyCode::Up);
+        let down = is_key_pressed(KeyCode::S) || is_key_pressed(KeyCode::Down);
+
+        // one row per mutator, then start
+        let start_row = MUTATORS.len() as i32;
+
+        if up {
+            self.menu_selected = max(0, self.menu_selected - 1);
+            self.menu_switch();
+        }
+
+        if down {
+            self.menu_selected = min(start_row, self.menu_selected + 1);
+            self.menu_switch();
+        }
+
+        if is_key_pressed(KeyCode::Escape) {
+            self.game_state = GameState::Characters;
+            self.menu_selected = 2;
+            self.menu_switch();
+            return;
+        }
+
+        if interact {
+            if self.menu_selected == start_row {
+                self.start_run();
+            } else {
+                self.rules.toggle(MUTATORS[self.menu_selected as usize]);
+                self.menu_switch();
+            }
+        }
+    }
+
+    pub fn mutators_draw(&mut self) {
+        clear_background(BLACK);
+        self.background_draw();
+        self.help_text();
+
+        let font_size = 12.0;
+        let x_center = DESIGN_WIDTH/2.0;
+
+        draw_text_centered("Mutators", x_center, 100.0, 25.0, &self.assets.font_monogram);
+
+        for (i, m) in MUTATORS.iter().enumerate() {
+            let check = if self.rules.has(*m) { "[x]" } else { "[ ]" };
+            let line = format!("{} {}  x{}", check, m.name(), m.score_mult());
+            let text = if i == self.menu_selected as usize { format!("> {} <", line) } else { line };
+            draw_text_centered(&text, x_center, 200.0 + (i as f32 * 70.0), font_size, &self.assets.font_monogram);
+            draw_text_centered_c(m.description(), x_center, 228.0 + (i as f32 * 70.0), 6.0, &self.assets.font_monogram, GRAY);
+        }
+
+        let start_row = MUTATORS.len();
+        let start = format!("Start  (score x{:.2})", self.rules.score_multiplier());
+        let text = if self.menu_selected as usize == start_row { format!("> {} <", start) } else { start };
+        draw_text_centered(&text, x_center, 220.0 + (start_row as f32 * 70.0), 15.0, &self.assets.font_monogram);
+    }
+}
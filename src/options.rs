@@ -5,6 +5,9 @@ use macroquad::audio::*;
 
 use crate::game::*;
 
+// how long a full background change takes with reduced motion on, which also caps how often it can happen
+pub const REDUCED_MOTION_FADE_TIME: f32 = 0.5;
+
 impl Game {
     pub fn level_bar(v: i32) -> String {
         let mut o = "I".repeat(v as usize);
@@ -27,7 +30,7 @@ impl Game {
         }
 
         if down {
-            self.menu_selected = min(3, self.menu_selected + 1);
+            self.menu_selected = min(5, self.menu_selected + 1);
             self.menu_switch();
         }
 
@@ -39,7 +42,7 @@ impl Game {
         if self.menu_selected == 0 {
             if interact {
                 self.game_state = GameState::MainMenu;
-                self.menu_selected = 1;
+                self.menu_selected = 3;
                 self.menu_switch();
             }
         }
@@ -81,6 +84,22 @@ impl Game {
                 self.should_save = true;
             }
         }
+
+        if self.menu_selected == 4 {
+            if interact {
+                self.colorblind = !self.colorblind;
+                self.menu_switch();
+                self.should_save = true;
+            }
+        }
+
+        if self.menu_selected == 5 {
+            if interact {
+                self.reduced_motion = !self.reduced_motion;
+                self.menu_switch();
+                self.should_save = true;
+            }
+        }
     }
 
     pub fn settings_draw(&mut self, bg_color: Color) {
@@ -92,21 +111,68 @@ impl Game {
         let x_center = DESIGN_WIDTH/2.0;
 
         let shoot_sound = if self.shooting_sound { String::from("Shooting sound  on") } else { String::from("Shooting sound  off") };
+        let colorblind = if self.colorblind { String::from("Color blind shapes  on") } else { String::from("Color blind shapes  off") };
+        let reduced_motion = if self.reduced_motion { String::from("Reduced motion  on") } else { String::from("Reduced motion  off") };
         let menu_txt = vec![
             String::from("Back"),
             format!("Music [{}]", Game::level_bar(self.music_level)),
             format!("Effects [{}]", Game::level_bar(self.effect_level)),
             shoot_sound, 
+            colorblind,
+            reduced_motion,
         ];
 
         for i in 0..menu_txt.len() {
             let text = if i == self.menu_selected as usize { format!("> {} <", menu_txt[i]) } else { menu_txt[i].to_string() };
 
-            if !self.shooting_sound && i == 3 {
+            if (!self.shooting_sound && i == 3) || (!self.colorblind && i == 4) || (!self.reduced_motion && i == 5) {
                 draw_text_centered(&text, x_center + 12.5, 200.0 + (i as f32 * 60.0), font_size, &self.assets.font_monogram);
             } else {
                 draw_text_centered(&text, x_center, 200.0 + (i as f32 * 60.0), font_size, &self.assets.font_monogram);
             }
         }
     }
-}
\ No newline at end of file
+
+    // asked once before the menu, the answer is saved like any other setting
+    pub fn first_launch_update(&mut self) {
+        self.background_update();
+        let interact = is_key_pressed(KeyCode::Space) || is_key_pressed(KeyCode::Enter);
+        let up = is_key_pressed(KeyCode::W) || is_key_pressed(KeyCode::Up);
+        let down = is_key_pressed(KeyCode::S) || is_key_pressed(KeyCode::Down);
+
+        if up {
+            self.menu_selected = max(0, self.menu_selected - 1);
+            self.menu_switch();
+        }
+
+        if down {
+            self.menu_selected = min(1, self.menu_selected + 1);
+            self.menu_switch();
+        }
+
+        if interact {
+            self.reduced_motion = self.menu_selected == 1;
+            self.should_save = true;
+            self.game_state = GameState::MainMenu;
+            self.menu_selected = 0;
+            self.menu_switch();
+        }
+    }
+
+    pub fn first_launch_draw(&mut self) {
+        clear_background(BLACK);
+        self.background_draw();
+
+        let x_center = DESIGN_WIDTH/2.0;
+        draw_text_centered("Before you start", x_center, 200.0, 20.0, &self.assets.font_monogram);
+        draw_text_centered_c("Switching colors flashes the whole screen and big hits shake it.", x_center, 300.0, 7.0, &self.assets.font_monogram, GRAY);
+        draw_text_centered_c("Reduced motion fades the background instead and turns shaking off.", x_center, 340.0, 7.0, &self.assets.font_monogram, GRAY);
+        draw_text_centered_c("You can change this later in Options.", x_center, 380.0, 7.0, &self.assets.font_monogram, GRAY);
+
+        let choices = ["Keep the full effects", "Reduce motion"];
+        for (i, c) in choices.iter().enumerate() {
+            let text = if i == self.menu_selected as usize { format!("> {} <", c) } else { c.to_string() };
+            draw_text_centered(&text, x_center, 500.0 + i as f32 * 60.0, 15.0, &self.assets.font_monogram);
+        }
+    }
+}
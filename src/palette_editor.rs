@@ -0,0 +1,257 @@
+use std::cmp::{max, min};
+
+use macroquad::prelude::*;
+
+use crate::game::*;
+use crate::colors::*;
+use crate::achievements::*;
+
+pub const MAX_CUSTOM_PALETTES: usize = 5;
+
+// below these the two states get hard to tell apart
+pub const MIN_HUE_GAP: f32 = 30.0;      // degrees
+pub const MIN_SATURATION: f32 = 0.2;    // under this hue barely shows
+pub const MIN_CONTRAST: f32 = 1.2;      // luminance contrast ratio between the states
+pub const MIN_VALUE: f32 = 0.35;        // darker than this and it sinks into its background
+
+// how fast holding a direction moves a slider, per second
+const HUE_SPEED: f32 = 90.0;
+const SV_SPEED: f32 = 0.5;
+
+const SLIDER_ROWS: i32 = 6;
+const SLIDER_WIDTH: f32 = 400.0;
+const SLIDER_SEGMENTS: i32 = 40;
+
+pub struct PaletteEditor {
+    pub hsv: [(f32, f32, f32); 2], // primary, secondary
+    pub editing: Option<usize>,    // index into palettes, None for a new one
+}
+
+impl PaletteEditor {
+    pub fn new(p: &ColorPalette, editing: Option<usize>) -> Self {
+        PaletteEditor {
+            hsv: [color_to_hsv(p.fg_primary), color_to_hsv(p.fg_secondary)],
+            editing,
+        }
+    }
+
+    pub fn palette(&self) -> ColorPalette {
+        let [(h1, s1, v1), (h2, s2, v2)] = self.hsv;
+        ColorPalette::create_from(hsv_to_color(h1, s1, v1), hsv_to_color(h2, s2, v2))
+    }
+}
+
+pub fn palette_warnings(p: &ColorPalette) -> Vec<&'static str> {
+    let (h1, s1, v1) = color_to_hsv(p.fg_primary);
+    let (h2, s2, v2) = color_to_hsv(p.fg_secondary);
+    let mut warnings = Vec::new();
+
+    let hue_gap = (h1 - h2).abs().min(360.0 - (h1 - h2).abs());
+    if hue_gap < MIN_HUE_GAP || s1 < MIN_SATURATION || s2 < MIN_SATURATION {
+        warnings.push("The hues are too close together");
+    }
+    if contrast_ratio(p.fg_primary, p.fg_secondary) < MIN_CONTRAST {
+        warnings.push("Both colors are about as bright, hard to tell apart for some players");
+    }
+    if v1 < MIN_VALUE || v2 < MIN_VALUE {
+        warnings.push("A color is too dark to see on its background");
+    }
+    warnings
+}
+
+impl Game {
+    pub fn custom_palette_count(&self) -> usize {
+        self.palettes.len() - PALETTE_NAMES.len()
+    }
+
+    pub fn open_palette_editor(&mut self) {
+        let idx = self.curr_palette_idx as usize;
+        let editing = if idx >= PALETTE_NAMES.len() { Some(idx) } else { None };
+
+        if editing.is_none() && self.custom_palette_count() >= MAX_CUSTOM_PALETTES {
+            self.toasts.push(Toast { text: String::from("No room, edit or delete a custom palette"), t: TOAST_TIME });
+            return;
+        }
+
+        // a locked palette shouldn't be a way to get its colors early
+        let start = if self.palette_unlocked(idx) { self.palettes[idx] } else { self.palette };
+        self.palette_editor = PaletteEditor::new(&start, editing);
+        self.game_state = GameState::PaletteEditor;
+        self.menu_selected = 0;
+        self.menu_switch();
+    }
+
+    fn close_palette_editor(&mut self) {
+        self.game_state = GameState::MainMenu;
+        self.menu_selected = 4;
+        self.curr_palette_idx = self.chosen_palette;
+        self.menu_switch();
+    }
+
+    fn last_editor_row(&self) -> i32 {
+        // save, then delete when there's something to delete
+        if self.palette_editor.editing.is_some() { SLIDER_ROWS + 1 } else { SLIDER_ROWS }
+    }
+
+    pub fn palette_editor_update(&mut self) {
+        self.background_update();
+        let interact = is_key_pressed(KeyCode::Space) || is_key_pressed(KeyCode::Enter);
+        let up = is_key_pressed(KeyCode::W) || is_key_pressed(KeyCode::Up);
+        let down = is_key_pressed(KeyCode::S) || is_key_pressed(KeyCode::Down);
+        let left = is_key_down(KeyCode::A) || is_key_down(KeyCode::Left);
+        let right = is_key_down(KeyCode::D) || is_key_down(KeyCode::Right);
+
+        if up {
+            self.menu_selected = max(0, self.menu_selected - 1);
+            self.menu_switch();
+        }
+
+        if down {
+            self.menu_selected = min(self.last_editor_row(), self.menu_selected + 1);
+            self.menu_switch();
+        }
+
+        if is_key_pressed(KeyCode::Escape) {
+            self.close_palette_editor();
+            return;
+        }
+
+        // sliders move while the key is held. Keyboard only like every other menu, macroquad
+        // has no gamepad input, so controller support waits until the game gets an input layer
+        if self.menu_selected < SLIDER_ROWS && (left || right) {
+            let dir = if right { 1.0 } else { -1.0 };
+            let dt = get_frame_time();
+            let (h, s, v) = &mut self.palette_editor.hsv[(self.menu_selected / 3) as usize];
+            match self.menu_selected % 3 {
+                0 => *h = (*h + dir * HUE_SPEED * dt).rem_euclid(360.0),
+                1 => *s = (*s + dir * SV_SPEED * dt).clamp(0.0, 1.0),
+                _ => *v = (*v + dir * SV_SPEED * dt).clamp(0.0, 1.0),
+            }
+        }
+
+        if interact && self.menu_selected == SLIDER_ROWS {
+            let p = self.palette_editor.palette();
+            let idx = match self.palette_editor.editing {
+                Some(i) => { self.palettes[i] = p; i }
+                None => { self.palettes.push(p); self.palettes.len() - 1 }
+            };
+            self.chosen_palette = idx as i32;
+            self.palette = p;
+            self.should_save = true;
+            self.close_palette_editor();
+        }
+
+        if interact && self.menu_selected == SLIDER_ROWS + 1 {
+            if let Some(i) = self.palette_editor.editing {
+                self.palettes.remove(i);
+                if self.chosen_palette == i as i32 {
+                    self.chosen_palette = 0;
+                } else if self.chosen_palette > i as i32 {
+                    self.chosen_palette -= 1;
+                }
+                self.palette = self.palettes[self.chosen_palette as usize];
+                self.should_save = true;
+                self.close_palette_editor();
+            }
+        }
+    }
+
+    fn slider_draw(&self, row: i32, label: &str, y: f32) {
+        let x = 350.0;
+        let (h, s, v) = self.palette_editor.hsv[(row / 3) as usize];
+        let selected = self.menu_selected == row;
+
+        let label = if selected { format!("> {}", label) } else { label.to_string() };
+        draw_text_ex(&label, 80.0, y + 25.0, TextParams { font: Some(&self.assets.font_monogram), font_size: 8, ..Default::default() });
+
+        // each segment shows the color you'd get with the slider there
+        let seg = SLIDER_WIDTH / SLIDER_SEGMENTS as f32;
+        for i in 0..SLIDER_SEGMENTS {
+            let t = (i as f32 + 0.5) / SLIDER_SEGMENTS as f32;
+            let c = match row % 3 {
+                0 => hsv_to_color(t * 360.0, s.max(0.5), v.max(0.5)),
+                1 => hsv_to_color(h, t, v),
+                _ => hsv_to_color(h, s, t),
+            };
+            draw_rectangle(x + i as f32 * seg, y, seg + 1.0, 30.0, c);
+        }
+        draw_rectangle_lines(x, y, SLIDER_WIDTH, 30.0, if selected { 4.0 } else { 2.0 }, if selected { WHITE } else { GRAY });
+
+        let t = match row % 3 {
+            0 => h / 360.0,
+            1 => s,
+            _ => v,
+        };
+        let mx = x + t * SLIDER_WIDTH;
+        draw_rectangle(mx - 3.0, y - 6.0, 6.0, 42.0, WHITE);
+    }
+
+    // A pretend bit of the arena in both states, used by the editor and the palette picker
+    pub fn palette_mock_draw(&self, p: &ColorPalette, x: f32, y: f32, w: f32, h: f32, alpha: f32) {
+        let with_alpha = |mut c: Color| { c.a *= alpha; c };
+        let halves = [(p.bg_primary, p.fg_primary, p.fg_secondary), (p.bg_secondary, p.fg_secondary, p.fg_primary)];
+
+        for (i, (bg, player, other)) in halves.iter().enumerate() {
+            let hx = x + i as f32 * w / 2.0;
+            let cx = hx + w / 4.0;
+            draw_rectangle(hx, y, w / 2.0, h, with_alpha(*bg));
+
+            // player in this state, enemies and bullets of both colors around it
+            draw_circle(cx, y + h * 0.6, h * 0.08, with_alpha(*player));
+            if self.colorblind {
+                let state = if i == 0 { ColorState::Primary } else { ColorState::Secondary };
+                draw_state_glyph(state, cx, y + h * 0.6, h * 0.08, with_alpha(BLACK));
+            }
+            draw_texture_ex(&self.assets.shooter, cx - w * 0.17, y + h * 0.15, with_alpha(*other),
+                DrawTextureParams { dest_size: Some(Vec2 { x: h * 0.2, y: h * 0.2 }), ..Default::default() });
+            draw_texture_ex(&self.assets.tower, cx + w * 0.05, y + h * 0.15, with_alpha(*player),
+                DrawTextureParams { dest_size: Some(Vec2 { x: h * 0.2, y: h * 0.2 }), ..Default::default() });
+            for b in 0..3 {
+                let bx = cx - w * 0.12 + b as f32 * w * 0.12;
+                let color = if b % 2 == 0 { *other } else { *player };
+                draw_circle(bx, y + h * 0.85, h * 0.03, with_alpha(color));
+            }
+        }
+        draw_rectangle_lines(x, y, w, h, 2.0, with_alpha(WHITE));
+    }
+
+    pub fn palette_editor_draw(&mut self) {
+        clear_background(BLACK);
+        self.background_draw();
+        self.help_text();
+
+        let title = match self.palette_editor.editing {
+            Some(i) => format!("Editing {}", self.palette_name(i)),
+            None => String::from("New palette"),
+        };
+        draw_text_centered(&title, DESIGN_WIDTH / 2.0, 100.0, 20.0, &self.assets.font_monogram);
+
+        let labels = ["Hue", "Saturation", "Brightness"];
+        for (state, (header, top)) in [("Primary", 190.0), ("Secondary", 430.0)].iter().enumerate() {
+            draw_text_ex(header, 80.0, *top, TextParams { font: Some(&self.assets.font_monogram), font_size: 10, ..Default::default() });
+            for (i, label) in labels.iter().enumerate() {
+                self.slider_draw((state * 3 + i) as i32, label, top + 30.0 + i as f32 * 60.0);
+            }
+        }
+
+        let save = if self.menu_selected == SLIDER_ROWS { "> Save <" } else { "Save" };
+        draw_text_centered(save, 550.0, 700.0, 12.0, &self.assets.font_monogram);
+        if self.palette_editor.editing.is_some() {
+            let delete = if self.menu_selected == SLIDER_ROWS + 1 { "> Delete <" } else { "Delete" };
+            draw_text_centered(delete, 550.0, 750.0, 12.0, &self.assets.font_monogram);
+        }
+
+        let p = self.palette_editor.palette();
+        self.palette_mock_draw(&p, 900.0, 170.0, 620.0, 350.0, 1.0);
+
+        let warnings = palette_warnings(&p);
+        for (i, w) in warnings.iter().enumerate() {
+            draw_text_centered_c(w, 1210.0, 580.0 + i as f32 * 35.0, 6.0, &self.assets.font_monogram, YELLOW);
+        }
+        if warnings.is_empty() {
+            draw_text_centered_c("Easy to tell apart", 1210.0, 580.0, 6.0, &self.assets.font_monogram, GRAY);
+        }
+        draw_text_ex("A/D - adjust", 50.0, 740.0,
+            TextParams { font: Some(&self.assets.font_monogram), font_size: 7, color: GRAY, ..Default::default()});
+    }
+}
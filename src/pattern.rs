@@ -0,0 +1,286 @@
+use macroquad::prelude::*;
+use macroquad::logging::error;
+use crate::game::*;
+use crate::bullet::*;
+use crate::colors::*;
+use crate::enemy::*;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum PatternKind {
+    Radial,
+    Spiral,
+    AimedFan,
+    Wall,
+    Ring,
+}
+
+#[derive(Clone)]
+pub struct Pattern {
+    pub name: String,
+    pub kind: PatternKind,
+    pub count: i32,
+    pub angle_step: f32,
+    pub speed: f32,
+    pub size: f32,
+    pub rate: f32,
+    pub colors: Vec<BulletColor>,
+    pub mods: BulletModifiers,
+}
+
+impl Default for Pattern {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            kind: PatternKind::Radial,
+            count: 8,
+            angle_step: 0.0,
+            speed: 250.0,
+            size: 6.0,
+            rate: 1.5,
+            colors: vec![BulletColor::Shooter],
+            mods: BulletModifiers::default(),
+        }
+    }
+}
+
+// An enemy written in the patterns file
+pub struct PatternEnemy {
+    pub name: String,
+    pub min_wave: i32,
+    pub enemy: Enemy,
+}
+
+fn parse_color(s: &str) -> Option<ColorState> {
+    match s {
+        "primary" => Some(ColorState::Primary),
+        "secondary" => Some(ColorState::Secondary),
+        _ => None,
+    }
+}
+
+fn parse_bullet_color(s: &str) -> Option<BulletColor> {
+    match s {
+        "primary" => Some(BulletColor::Primary),
+        "secondary" => Some(BulletColor::Secondary),
+        "shooter" => Some(BulletColor::Shooter),
+        "opposite" => Some(BulletColor::OppositePlayer),
+        _ => None,
+    }
+}
+
+fn parse_kind(s: &str) -> Option<PatternKind> {
+    match s {
+        "radial" => Some(PatternKind::Radial),
+        "spiral" => Some(PatternKind::Spiral),
+        "fan" => Some(PatternKind::AimedFan),
+        "wall" => Some(PatternKind::Wall),
+        "ring" => Some(PatternKind::Ring),
+        _ => None,
+    }
+}
+
+fn parse_pattern(name: &str, kind: &str, args: &[&str]) -> Result<Pattern, String> {
+    let mut p = Pattern {
+        name: name.to_string(),
+        kind: parse_kind(kind).ok_or(format!("unknown pattern kind '{}'", kind))?,
+        ..Default::default()
+    };
+
+    for arg in args {
+        let (key, value) = arg.split_once('=').ok_or(format!("expected key=value, got '{}'", arg))?;
+        let num = || value.parse::<f32>().map_err(|_| format!("bad number for {}: '{}'", key, value));
+        let int = || value.parse::<i32>().map_err(|_| format!("bad whole number for {}: '{}'", key, value));
+        match key {
+            "count" => p.count = int()?,
+            "step" => p.angle_step = num()?,
+            "speed" => p.speed = num()?,
+            "size" => p.size = num()?,
+            "rate" => p.rate = num()?,
+            "pierce" => p.mods.pierce = int()?,
+            "bounce" => p.mods.bounces = int()?,
+            "homing" => p.mods.homing = num()?,
+            "split" => p.mods.split = int()?,
+            "lifetime" => p.mods.lifetime = num()?,
+            "accel" => p.mods.acceleration = num()?,
+            "colors" => {
+                p.colors = value.split(',')
+                    .map(|c| parse_bullet_color(c).ok_or(format!("unknown color '{}'", c)))
+                    .collect::<Result<Vec<_>, _>>()?;
+            }
+            _ => return Err(format!("unknown pattern key '{}'", key)),
+        }
+    }
+
+    if p.count <= 0 || p.colors.is_empty() || p.rate <= 0.0 {
+        return Err(String::from("count, colors and rate must not be empty"));
+    }
+    // two of the wall are left out for the gap, any fewer than three and nothing fires
+    if p.kind == PatternKind::Wall && p.count < 3 {
+        return Err(format!("wall needs a count of at least 3, got {}", p.count));
+    }
+    Ok(p)
+}
+
+fn parse_enemy(name: &str, args: &[&str], patterns: &[Pattern]) -> Result<PatternEnemy, String> {
+    let mut e = PatternEnemy {
+        name: name.to_string(),
+        min_wave: 1,
+        enemy: Enemy { kind: EnemyType::PatternShooter, can_collide: true, ..Default::default() },
+    };
+
+    for arg in args {
+        let (key, value) = arg.split_once('=').ok_or(format!("expected key=value, got '{}'", arg))?;
+        let num = || value.parse::<f32>().map_err(|_| format!("bad number for {}: '{}'", key, value));
+        let int = || value.parse::<i32>().map_err(|_| format!("bad whole number for {}: '{}'", key, value));
+        match key {
+            "pattern" => {
+                let idx = patterns.iter().position(|p| p.name == value)
+                    .ok_or(format!("unknown pattern '{}'", value))?;
+                e.enemy.pattern = Some(idx);
+                e.enemy.attack_speed = patterns[idx].rate;
+            }
+            "health" => e.enemy.health = num()?,
+            "size" => e.enemy.size = num()?,
+            "score" => e.enemy.score = int()?,
+            "wave" => e.min_wave = int()?,
+            "color" => e.enemy.state = parse_color(value).ok_or(format!("unknown color '{}'", value))?,
+            "flip" => {
+                // flip=switch or flip=<seconds>
+                if value == "switch" {
+                    e.enemy.flip = FlipMode::OnSwitch;
+                } else {
+                    e.enemy.flip = FlipMode::Timer;
+                    e.enemy.flip_tmax = num()?;
+                }
+            }
+            "immune" => e.enemy.immune = Some(parse_color(value).ok_or(format!("unknown color '{}'", value))?),
+            _ => return Err(format!("unknown enemy key '{}'", key)),
+        }
+    }
+
+    if e.enemy.pattern.is_none() {
+        return Err(String::from("enemy needs a pattern"));
+    }
+    Ok(e)
+}
+
+// Broken lines are logged and skipped so one typo doesn't take out every pattern
+pub fn load_patterns(data: &str) -> (Vec<Pattern>, Vec<PatternEnemy>) {
+    let mut patterns = Vec::new();
+    let mut enemies = Vec::new();
+
+    for (i, line) in data.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let result = match words.as_slice() {
+            ["pattern", name, kind, args @ ..] => parse_pattern(name, kind, args).map(|p| patterns.push(p)),
+            ["enemy", name, args @ ..] => parse_enemy(name, args, &patterns).map(|e| enemies.push(e)),
+            _ => Err(String::from("expected 'pattern' or 'enemy'")),
+        };
+
+        if let Err(err) = result {
+            error!("patterns.txt line {}: {}", i + 1, err);
+        }
+    }
+
+    (patterns, enemies)
+}
+
+impl Game {
+    pub fn emit_pattern(&mut self, e: &mut Enemy, pattern: usize) {
+        let p = self.patterns[pattern].clone();
+        let origin = Vec2 { x: e.x + e.size / 2.0, y: e.y + e.size / 2.0 };
+        let target = Vec2 { x: self.player.x + self.player.size / 2.0, y: self.player.y + self.player.size / 2.0 };
+        let aim = (target - origin).normalize_or_zero();
+        let volley_color = p.colors[e.emit_count as usize % p.colors.len()];
+
+        // (position, direction, color) for every bullet in this volley
+        let mut shots: Vec<(Vec2, Vec2, BulletColor)> = Vec::new();
+        match p.kind {
+            PatternKind::Radial | PatternKind::Ring => {
+                let step = 360.0 / p.count as f32;
+                for i in 0..p.count {
+                    let dir = rotate_vec(Vec2::X, e.emit_angle + step * i as f32);
+                    let color = if p.kind == PatternKind::Ring { p.colors[i as usize % p.colors.len()] } else { volley_color };
+                    shots.push((origin, dir, color));
+                }
+                // offset every other volley so they don't line up
+                e.emit_angle += step / 2.0;
+            }
+            PatternKind::Spiral => {
+                let step = 360.0 / p.count as f32;
+                for i in 0..p.count {
+                    shots.push((origin, rotate_vec(Vec2::X, e.emit_angle + step * i as f32), volley_color));
+                }
+                e.emit_angle += p.angle_step;
+            }
+            PatternKind::AimedFan => {
+                let start = -p.angle_step * (p.count - 1) as f32 / 2.0;
+                for i in 0..p.count {
+                    shots.push((origin, rotate_vec(aim, start + p.angle_step * i as f32), volley_color));
+                }
+            }
+            PatternKind::Wall => {
+                // line of bullets across the aim direction, with a gap to slip through
+                let side = aim.perp();
+                let gap = self.run_rng.below((p.count - 1) as usize) as i32;
+                let start = -p.angle_step * (p.count - 1) as f32 / 2.0;
+                for i in 0..p.count {
+                    if i == gap || i == gap + 1 {
+                        continue;
+                    }
+                    let pos = origin + side * (start + p.angle_step * i as f32);
+                    shots.push((pos, aim, volley_color));
+                }
+            }
+        }
+
+        for (pos, dir, color) in shots {
+            let mut b = Bullet::new(pos.x, pos.y, dir, BulletType::Enemy)
+                .with_size(p.size)
+                .with_speed(p.speed)
+                .with_mods(p.mods);
+            b.state = self.resolve_bullet_color(color, e.state);
+            b.source = e.archetype;
+            self.bullets.push(b);
+        }
+        e.emit_count += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pattern(kind: &str, args: &str) -> Result<Pattern, String> {
+        parse_pattern("test", kind, &args.split_whitespace().collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn parses_modifiers() {
+        let p = pattern("fan", "count=3 step=10 colors=primary pierce=2 bounce=1 split=3 homing=40").unwrap();
+        assert_eq!((p.count, p.mods.pierce, p.mods.bounces, p.mods.split), (3, 2, 1, 3));
+        assert_eq!(p.mods.homing, 40.0);
+    }
+
+    #[test]
+    fn whole_number_keys() {
+        for args in ["count=2.5", "pierce=1.5", "bounce=x", "split=0.5"] {
+            let err = pattern("radial", &format!("{} colors=primary", args)).err().unwrap();
+            assert!(err.starts_with("bad whole number"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn wall_needs_a_gap_and_bullets() {
+        for count in 1..3 {
+            assert!(pattern("wall", &format!("count={} colors=primary", count)).is_err());
+        }
+        assert!(pattern("wall", "count=3 colors=primary").is_ok());
+        assert!(pattern("radial", "count=1 colors=primary").is_ok());
+    }
+}
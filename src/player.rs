@@ -3,6 +3,8 @@ use macroquad::audio::*;
 use crate::game::*;
 use crate::bullet::*;
 use crate::colors::*;
+use crate::collision::Shape;
+use crate::ability::*;
 
 pub struct Player {
     pub max_health: i32,
@@ -26,6 +28,13 @@ pub struct Player {
     pub shoot_t: f32,
     pub melee_t: f32,
     pub melee_range: f32,
+    pub bullet_mods: BulletModifiers,
+    pub laser_level: i32,
+    pub ability: Ability,
+    pub invuln_t: f32,
+    pub shield: bool,
+    pub dash_t: f32,
+    pub dash_dir: Vec2,
 
     pub heal_from_b: i32,
 }
@@ -54,6 +63,13 @@ impl Default for Player {
             shoot_t: 0.0,
             melee_t: 0.0,
             melee_range: 80.0,
+            bullet_mods: BulletModifiers::default(),
+            laser_level: 0,
+            ability: Ability::new(AbilityKind::Dash),
+            invuln_t: 0.0,
+            shield: false,
+            dash_t: 0.0,
+            dash_dir: Vec2::ZERO,
 
             heal_from_b: 1,
         }
@@ -61,12 +77,15 @@ impl Default for Player {
 }
 
 impl Player {
-    pub fn get_rect(&self) -> Rect {
-        Rect {
-            x: self.x,
-            y: self.y,
-            w: self.size,
-            h: self.size
+    pub fn new(ability: AbilityKind) -> Player {
+        Player { ability: Ability::new(ability), ..Default::default() }
+    }
+
+    pub fn get_shape(&self) -> Shape {
+        Shape::Circle {
+            x: self.x + self.size / 2.0,
+            y: self.y + self.size / 2.0,
+            r: self.size / 2.0,
         }
     }
 }
@@ -74,11 +93,16 @@ impl Player {
 impl Game {
 
     pub fn player_draw(&self) {
-        let color = match self.color_state {
+        let mut color = match self.color_state {
             ColorState::Primary => self.palette.fg_primary,
             ColorState::Secondary => self.palette.fg_secondary
         };
 
+        // blink while invulnerable
+        if self.player.invuln_t > 0.0 && (self.player.invuln_t * 20.0) as i32 % 2 == 0 {
+            color.a = 0.3;
+        }
+
         let center_x = self.player.x + self.player.size/2.0;
         let center_y = self.player.y + self.player.size/2.0;
 
@@ -101,6 +125,9 @@ impl Game {
         let offset = 20.0;
         let scale = 3.0;
         draw_circle(self.player.x + self.player.size / 2.0, self.player.y + self.player.size/2.0, self.player.size / 2.0, color);
+        if self.colorblind {
+            draw_state_glyph(self.color_state, center_x, center_y, self.player.size * 0.5, BLACK);
+        }
 
        
 
@@ -168,32 +195,33 @@ impl Game {
             if self.player.shoot_t <= 0.0 {
 
                 play_sound(&self.assets.shoot, PlaySoundParams { looped: false, volume: self.effect_level as f32 / 10.0 });
-                // rotate by spread randomly
-                let deg_projectile = 2.0;
-                let offset = deg_projectile/2.0;
 
-                for i in 0..self.player.projectiles as i32 {
+                if self.player.laser_level > 0 {
+                    // laser weapon fires slower, but hits everything in the line
+                    self.player_fire_laser(shoot_dir);
+                    self.player.shoot_t += self.player.attack_speed * 4.0;
+                } else {
+                    // rotate by spread randomly
+                    let deg_projectile = 2.0;
+                    let offset = deg_projectile/2.0;
+
+                    for i in 0..self.player.projectiles as i32 {
                     
-                    let actual_spread = rand::gen_range(-self.player.spread, self.player.spread);
-                    let  dir = rotate_vec(Vec2 { x: self.player.shoot_dx, y: self.player.shoot_dy}, actual_spread);
-                    let mut dir = rotate_vec(dir, -offset + i as f32*deg_projectile);
-                    dir = dir.normalize_or_zero();
-
-                    self.bullets.push(Bullet::new(
-                        1, // damage
-                        self.player.x + 15.0, // x
-                        self.player.y + 15.0, // y
-                        dir.x, // dx
-                        dir.y, // dy
-                        self.player.bullet_size,
-                        self.player.bullet_speed,
-                        BulletType::Player, // kind
-                    ));
+                        let actual_spread = self.run_rng.range(-self.player.spread, self.player.spread);
+                        let  dir = rotate_vec(Vec2 { x: self.player.shoot_dx, y: self.player.shoot_dy}, actual_spread);
+                        let mut dir = rotate_vec(dir, -offset + i as f32*deg_projectile);
+                        dir = dir.normalize_or_zero();
+
+                        let mut b = Bullet::new(self.player.x + 15.0, self.player.y + 15.0, dir, BulletType::Player)
+                            .with_size(self.player.bullet_size)
+                            .with_speed(self.player.bullet_speed)
+                            .with_mods(self.player.bullet_mods);
+                        b.state = self.color_state;
+                        self.bullets.push(b);
+                    }
+
+                    self.player.shoot_t += self.player.attack_speed;
                 }
-
-
-
-                self.player.shoot_t += self.player.attack_speed;
             }
 
         }
@@ -206,6 +234,11 @@ impl Game {
             self.player.melee_t -= get_frame_time();
         }
 
+        if self.player.dash_t > 0.0 {
+            self.player.dx = self.player.dash_dir.x * self.player.move_speed * 4.0;
+            self.player.dy = self.player.dash_dir.y * self.player.move_speed * 4.0;
+        }
+
         self.player.x += self.player.dx * dt;
         self.player.y += self.player.dy * dt;
     }
@@ -0,0 +1,59 @@
+use macroquad::prelude::*;
+
+use crate::game::*;
+use crate::rules::*;
+use crate::survival::*;
+
+// What the score screen shows after a run
+pub struct RunResult {
+    pub mode: GameMode,
+    pub score: i32,
+    pub wave: i32,
+    pub time: f32,
+    pub mutators: Vec<Mutator>,
+    pub multiplier: f32,
+    pub new_best: bool,
+}
+
+impl Game {
+    pub fn score_update(&mut self) {
+        self.background_update();
+        let interact = is_key_pressed(KeyCode::Space) || is_key_pressed(KeyCode::Enter);
+
+        if interact || is_key_pressed(KeyCode::Escape) {
+            self.game_state = GameState::MainMenu;
+            self.menu_switch();
+        }
+    }
+
+    pub fn score_draw(&mut self) {
+        clear_background(BLACK);
+        self.background_draw();
+
+        let x_center = DESIGN_WIDTH/2.0;
+        let Some(r) = &self.last_run else { return };
+
+        let title = match r.mode {
+            GameMode::Classic => "Run over",
+            GameMode::Survival => "Survival over",
+            GameMode::Daily => "Daily challenge over",
+        };
+        draw_text_centered(title, x_center, 150.0, 25.0, &self.assets.font_monogram);
+
+        draw_text_centered(&format!("Score: {}", r.score), x_center, 300.0, 18.0, &self.assets.font_monogram);
+        if r.new_best {
+            draw_text_centered("New best!", x_center, 360.0, 10.0, &self.assets.font_monogram);
+        }
+
+        let progress = match r.mode {
+            GameMode::Survival => format!("Survived {}", format_time(r.time)),
+            _ => format!("Reached wave {}", r.wave),
+        };
+        draw_text_centered(&progress, x_center, 440.0, 12.0, &self.assets.font_monogram);
+
+        draw_text_centered(&mutators_name(&r.mutators), x_center, 540.0, 8.0, &self.assets.font_monogram);
+        draw_text_centered(&format!("Score x{:.2}", r.multiplier), x_center, 580.0, 8.0, &self.assets.font_monogram);
+
+        draw_text_centered_c("Space/Enter - continue", x_center, 760.0, 7.0, &self.assets.font_monogram, GRAY);
+    }
+}
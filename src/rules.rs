@@ -0,0 +1,171 @@
+use crate::colors::*;
+use crate::enemy::*;
+use crate::game::*;
+
+// damage multipliers with polarity rules on
+pub const POLARITY_OPPOSITE_MULT: f32 = 2.0;
+pub const POLARITY_SAME_MULT: f32 = 0.5;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Mutator {
+    GlassCannon,
+    NoSwitch,
+    FastEnemies,
+    OnlyTowers,
+    NoHeal,
+    RandomFlips,
+}
+
+pub const MUTATORS: [Mutator; 6] = [
+    Mutator::GlassCannon,
+    Mutator::NoSwitch,
+    Mutator::FastEnemies,
+    Mutator::OnlyTowers,
+    Mutator::NoHeal,
+    Mutator::RandomFlips,
+];
+
+impl Mutator {
+    pub fn name(&self) -> &str {
+        match self {
+            Mutator::GlassCannon => "Glass cannon",
+            Mutator::NoSwitch => "No switch",
+            Mutator::FastEnemies => "Enemies x2 speed",
+            Mutator::OnlyTowers => "Only towers",
+            Mutator::NoHeal => "Bullets heal nothing",
+            Mutator::RandomFlips => "Random flips",
+        }
+    }
+
+    pub fn description(&self) -> &str {
+        match self {
+            Mutator::GlassCannon => "1 HP, x2 damage",
+            Mutator::NoSwitch => "You're stuck with your first color",
+            Mutator::FastEnemies => "Enemies move twice as fast",
+            Mutator::OnlyTowers => "Every enemy is a circle tower",
+            Mutator::NoHeal => "Same color bullets don't heal",
+            Mutator::RandomFlips => "Enemies flip colors on their own",
+        }
+    }
+
+    pub fn score_mult(&self) -> f32 {
+        match self {
+            Mutator::GlassCannon => 2.0,
+            Mutator::NoSwitch => 1.5,
+            Mutator::FastEnemies => 1.5,
+            Mutator::OnlyTowers => 1.25,
+            Mutator::NoHeal => 1.3,
+            Mutator::RandomFlips => 1.25,
+        }
+    }
+
+    // short name for save data
+    pub fn id(&self) -> &str {
+        match self {
+            Mutator::GlassCannon => "glass",
+            Mutator::NoSwitch => "noswitch",
+            Mutator::FastEnemies => "fast",
+            Mutator::OnlyTowers => "towers",
+            Mutator::NoHeal => "noheal",
+            Mutator::RandomFlips => "flips",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Mutator> {
+        MUTATORS.iter().copied().find(|m| m.id() == id)
+    }
+}
+
+// Mutators saved as ids joined with '+'
+pub fn mutators_to_string(mutators: &[Mutator]) -> String {
+    mutators.iter().map(|m| m.id()).collect::<Vec<_>>().join("+")
+}
+
+pub fn parse_mutators(s: &str) -> Vec<Mutator> {
+    s.split('+').filter_map(Mutator::from_id).collect()
+}
+
+pub fn mutators_name(mutators: &[Mutator]) -> String {
+    if mutators.is_empty() {
+        return String::from("No mutators");
+    }
+    mutators.iter().map(|m| m.name()).collect::<Vec<_>>().join(", ")
+}
+
+// Optional rules picked before a run, classic is everything off
+pub struct GameRules {
+    pub polarity: bool,
+    pub mutators: Vec<Mutator>,
+}
+
+impl GameRules {
+    pub fn default() -> Self {
+        GameRules {
+            polarity: false,
+            mutators: Vec::new(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        if self.polarity { "Polarity rules" } else { "Classic rules" }
+    }
+
+    pub fn has(&self, m: Mutator) -> bool {
+        self.mutators.contains(&m)
+    }
+
+    pub fn toggle(&mut self, m: Mutator) {
+        match self.mutators.iter().position(|x| *x == m) {
+            Some(i) => { self.mutators.remove(i); }
+            None => self.mutators.push(m),
+        }
+    }
+
+    pub fn score_multiplier(&self) -> f32 {
+        self.mutators.iter().map(|m| m.score_mult()).product()
+    }
+}
+
+impl Game {
+    pub fn enemy_speed_mult(&self) -> f32 {
+        if self.rules.has(Mutator::FastEnemies) { 2.0 } else { 1.0 }
+    }
+
+    // set up the player for the run's mutators, after the character is applied
+    pub fn apply_mutators(&mut self) {
+        if self.rules.has(Mutator::GlassCannon) {
+            self.player.max_health = 1;
+            self.player.health = 1;
+            self.player.damage *= 2.0;
+        }
+    }
+
+    // mutators that change an enemy as it comes in
+    pub fn mutate_enemy(&self, mut e: Enemy) -> Enemy {
+        if self.rules.has(Mutator::OnlyTowers) && e.kind != EnemyType::StaticCircleAttack {
+            if let Some(tower) = self.archetype("tower") {
+                e = Enemy { x: e.x, y: e.y, state: e.state, ..tower };
+            }
+        }
+        if self.rules.has(Mutator::RandomFlips) && e.flip == FlipMode::Never && self.run_rng.below(2) == 0 {
+            e.flip = FlipMode::Timer;
+            e.flip_tmax = self.run_rng.range(2.0, 5.0);
+        }
+        e
+    }
+
+    // how much of a hit an enemy takes from a bullet of this color
+    pub fn damage_multiplier(&self, bullet: ColorState, e: &Enemy) -> f32 {
+        if !self.rules.polarity {
+            return 1.0;
+        }
+
+        if e.immune == Some(bullet) {
+            0.0
+        } else if e.state == bullet {
+            POLARITY_SAME_MULT
+        } else {
+            POLARITY_OPPOSITE_MULT
+        }
+    }
+}
@@ -0,0 +1,417 @@
+use macroquad::prelude::*;
+use macroquad::logging::error;
+use crate::game::*;
+use crate::enemy::*;
+use crate::spawn::*;
+use crate::pattern::PatternEnemy;
+use crate::survival::GameMode;
+
+// Names the waves file can use for the built in enemies, index into enemy_list and the
+// kind that should be there, the tests catch the two drifting apart
+pub const ARCHETYPES: [(&str, usize, EnemyType); 11] = [
+    ("shooter", 0, EnemyType::FollowShootEnemy),
+    ("tower", 1, EnemyType::StaticCircleAttack),
+    ("swarm_primary", 2, EnemyType::FollowEnemy),
+    ("swarm_secondary", 3, EnemyType::FollowEnemy),
+    ("charger", 10, EnemyType::Charger),
+    ("splitter", 11, EnemyType::Splitter),
+    ("teleporter", 12, EnemyType::Teleporter),
+    ("shielded", 13, EnemyType::Shielded),
+    ("flicker", 14, EnemyType::FollowEnemy),
+    ("mimic", 15, EnemyType::FollowEnemy),
+    ("sniper", 16, EnemyType::Sniper),
+];
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Trigger {
+    Time(f32),    // seconds after the previous group came in, or as soon as nothing is alive
+    Cleared,      // nothing from earlier groups is left alive
+    Health(f32),  // enemies on screen are down to this fraction of their health when the previous group came in
+}
+
+#[derive(Clone)]
+pub struct SpawnGroup {
+    pub archetype: String,
+    pub count: i32,
+    pub formation: Formation,
+    pub delay: f32, // wait after the trigger before the telegraphs show up
+    pub trigger: Trigger,
+}
+
+#[derive(Clone)]
+pub struct ScriptedWave {
+    pub groups: Vec<SpawnGroup>,
+}
+
+impl ScriptedWave {
+    pub fn enemy_count(&self) -> i32 {
+        self.groups.iter().map(|g| g.count).sum()
+    }
+}
+
+fn parse_formation(s: &str) -> Option<Formation> {
+    match s {
+        "ring" => Some(Formation::Ring),
+        "line" => Some(Formation::Line),
+        "edge" => Some(Formation::Edge),
+        "cluster" => Some(Formation::Cluster),
+        _ => None,
+    }
+}
+
+fn parse_trigger(s: &str) -> Result<Trigger, String> {
+    let bad = || format!("bad trigger '{}'", s);
+    match s.split_once(':') {
+        None if s == "cleared" => Ok(Trigger::Cleared),
+        Some(("time", t)) => Ok(Trigger::Time(t.parse().map_err(|_| bad())?)),
+        Some(("hp", pct)) => {
+            let pct: f32 = pct.parse().map_err(|_| bad())?;
+            if !(0.0..=100.0).contains(&pct) {
+                return Err(format!("hp trigger must be 0-100, got {}", pct));
+            }
+            Ok(Trigger::Health(pct / 100.0))
+        }
+        _ => Err(bad()),
+    }
+}
+
+fn parse_group(archetype: &str, args: &[&str], names: &[&str]) -> Result<SpawnGroup, String> {
+    if !names.contains(&archetype) {
+        return Err(format!("unknown archetype '{}'", archetype));
+    }
+
+    let mut g = SpawnGroup {
+        archetype: archetype.to_string(),
+        count: 1,
+        formation: Formation::Cluster,
+        delay: 0.0,
+        trigger: Trigger::Time(0.0),
+    };
+
+    for arg in args {
+        let (key, value) = arg.split_once('=').ok_or(format!("expected key=value, got '{}'", arg))?;
+        let num = || value.parse::<f32>().map_err(|_| format!("bad number for {}: '{}'", key, value));
+        match key {
+            "count" => g.count = num()? as i32,
+            "delay" => g.delay = num()?,
+            "formation" => g.formation = parse_formation(value).ok_or(format!("unknown formation '{}'", value))?,
+            "trigger" => g.trigger = parse_trigger(value)?,
+            _ => return Err(format!("unknown group key '{}'", key)),
+        }
+    }
+
+    if g.count <= 0 || g.delay < 0.0 {
+        return Err(String::from("count must be positive and delay not negative"));
+    }
+    Ok(g)
+}
+
+// Unlike patterns a bad line rejects the whole file, half a wave is worse than none
+pub fn parse_waves(data: &str, names: &[&str]) -> Result<Vec<ScriptedWave>, String> {
+    let mut waves: Vec<ScriptedWave> = Vec::new();
+
+    for (i, line) in data.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let result = match words.as_slice() {
+            ["wave", n] => {
+                match n.parse::<usize>() {
+                    Ok(n) if n == waves.len() + 1 => {
+                        waves.push(ScriptedWave { groups: Vec::new() });
+                        Ok(())
+                    }
+                    _ => Err(format!("expected 'wave {}'", waves.len() + 1)),
+                }
+            }
+            ["group", archetype, args @ ..] => match waves.last_mut() {
+                Some(w) => parse_group(archetype, args, names).map(|g| w.groups.push(g)),
+                None => Err(String::from("group before the first wave")),
+            },
+            _ => Err(String::from("expected 'wave' or 'group'")),
+        };
+
+        if let Err(err) = result {
+            return Err(format!("waves.txt line {}: {}", i + 1, err));
+        }
+    }
+
+    if let Some(n) = waves.iter().position(|w| w.groups.is_empty()) {
+        return Err(format!("waves.txt: wave {} has no groups", n + 1));
+    }
+    Ok(waves)
+}
+
+// Steps through the groups of one scripted wave, doesn't touch the game so it can run headless
+pub struct ScriptRunner {
+    pub next_group: usize,
+    pub since_last: f32,      // time since the last group spawned
+    pub delay_t: f32,         // counting down the delay of a triggered group
+    pub triggered: bool,
+    pub group_health: f32,    // health on screen, spawning included, just after the last group came in
+}
+
+impl ScriptRunner {
+    pub fn new() -> Self {
+        ScriptRunner { next_group: 0, since_last: 0.0, delay_t: 0.0, triggered: false, group_health: 0.0 }
+    }
+
+    // alive counts enemies on screen and still spawning, index of a group to spawn now if any
+    pub fn update(&mut self, wave: &ScriptedWave, dt: f32, alive: usize, alive_health: f32) -> Option<usize> {
+        let group = wave.groups.get(self.next_group)?;
+        self.since_last += dt;
+
+        if !self.triggered {
+            self.triggered = match group.trigger {
+                // no point waiting on a timer with nothing to fight
+                Trigger::Time(t) => self.since_last >= t || alive == 0,
+                Trigger::Cleared => alive == 0,
+                Trigger::Health(frac) => self.group_health <= 0.0 || alive_health <= self.group_health * frac,
+            };
+            self.delay_t = group.delay;
+        }
+        if !self.triggered {
+            return None;
+        }
+
+        self.delay_t -= dt;
+        if self.delay_t > 0.0 {
+            return None;
+        }
+
+        self.triggered = false;
+        self.since_last = 0.0;
+        self.next_group += 1;
+        Some(self.next_group - 1)
+    }
+
+    // alive_health from this update plus whatever the new group brings
+    pub fn set_group_health(&mut self, health: f32) {
+        self.group_health = health;
+    }
+}
+
+pub fn load_waves(data: &str, names: &[&str]) -> Vec<ScriptedWave> {
+    match parse_waves(data, names) {
+        Ok(waves) => waves,
+        Err(err) => {
+            // everything falls back to procedural waves
+            error!("{}", err);
+            Vec::new()
+        }
+    }
+}
+
+// every name a wave script can spawn
+pub fn archetype_names(pattern_enemies: &[PatternEnemy]) -> Vec<&str> {
+    ARCHETYPES.iter().map(|(n, _, _)| *n)
+        .chain(pattern_enemies.iter().map(|p| p.name.as_str()))
+        .collect()
+}
+
+impl Game {
+    // tagged with its place in archetype_names so kills land in the bestiary
+    pub fn archetype(&self, name: &str) -> Option<Enemy> {
+        if let Some(i) = ARCHETYPES.iter().position(|(n, _, _)| *n == name) {
+            return Some(Enemy { archetype: Some(i), ..self.enemy_list[ARCHETYPES[i].1] });
+        }
+        let i = self.pattern_enemies.iter().position(|p| p.name == name)?;
+        Some(Enemy { archetype: Some(ARCHETYPES.len() + i), ..self.pattern_enemies[i].enemy })
+    }
+
+    pub fn scripted_wave(&self) -> Option<&ScriptedWave> {
+        if self.mode == GameMode::Survival {
+            return self.generated_wave.as_ref();
+        }
+        if self.wave.current < 1 {
+            return None;
+        }
+        self.waves.get(self.wave.current as usize - 1).or(self.generated_wave.as_ref())
+    }
+
+    pub fn update_wave_script(&mut self) {
+        let Some(wave) = self.scripted_wave().cloned() else { return };
+
+        let alive = self.enemies.len() + self.enemy_spawn.len();
+        let alive_health: f32 = self.enemies.iter().map(|e| e.health).sum::<f32>()
+            + self.enemy_spawn.iter().map(|s| s.to_spawn.health).sum::<f32>();
+
+        let Some(idx) = self.wave_runner.update(&wave, self.world_dt(), alive, alive_health) else { return };
+        let group = &wave.groups[idx];
+        self.wave.enemy_remaining -= group.count;
+        let Some(enemy) = self.archetype(&group.archetype) else { return };
+
+        self.wave_runner.set_group_health(alive_health + enemy.health * group.count as f32);
+        self.spawn_group(vec![enemy; group.count as usize], group.formation);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::colors::ColorState;
+
+    const NAMES: [&str; 2] = ["shooter", "tower"];
+    const DT: f32 = 0.1;
+
+    fn group(trigger: Trigger, delay: f32) -> SpawnGroup {
+        SpawnGroup { archetype: String::from("shooter"), count: 1, formation: Formation::Cluster, delay, trigger }
+    }
+
+    fn wave(groups: Vec<SpawnGroup>) -> ScriptedWave {
+        ScriptedWave { groups }
+    }
+
+    // steps the runner until it spawns something, None if it never does within the limit
+    fn steps_until_spawn(r: &mut ScriptRunner, w: &ScriptedWave, alive: usize, alive_health: f32, limit: usize) -> Option<usize> {
+        (1..=limit).find(|_| r.update(w, DT, alive, alive_health).is_some())
+    }
+
+    #[test]
+    fn parses_waves() {
+        let data = "\
+            # a comment\n\
+            wave 1\n\
+            group shooter count=3 formation=ring trigger=time:2 delay=0.5\n\
+            \n\
+            wave 2\n\
+            group tower\n\
+            group shooter formation=edge trigger=hp:25\n\
+            group tower trigger=cleared\n";
+        let waves = parse_waves(data, &NAMES).unwrap();
+
+        assert_eq!(waves.len(), 2);
+        let g = &waves[0].groups[0];
+        assert_eq!((g.archetype.as_str(), g.count, g.delay), ("shooter", 3, 0.5));
+        assert!(g.formation == Formation::Ring);
+        assert_eq!(g.trigger, Trigger::Time(2.0));
+
+        // defaults
+        let g = &waves[1].groups[0];
+        assert_eq!((g.count, g.delay, g.trigger), (1, 0.0, Trigger::Time(0.0)));
+        assert!(g.formation == Formation::Cluster);
+
+        assert_eq!(waves[1].groups[1].trigger, Trigger::Health(0.25));
+        assert_eq!(waves[1].groups[2].trigger, Trigger::Cleared);
+        assert_eq!(waves[1].enemy_count(), 3);
+    }
+
+    #[test]
+    fn rejects_bad_lines() {
+        let bad = [
+            ("wave 2\ngroup shooter", "line 1: expected 'wave 1'"),
+            ("wave 1\ngroup shooter\nwave 1\ngroup shooter", "line 3: expected 'wave 2'"),
+            ("group shooter", "line 1: group before the first wave"),
+            ("wave 1\nspawn shooter", "line 2: expected 'wave' or 'group'"),
+            ("wave 1\ngroup dragon", "line 2: unknown archetype 'dragon'"),
+            ("wave 1\ngroup shooter size=3", "line 2: unknown group key 'size'"),
+            ("wave 1\ngroup shooter count", "line 2: expected key=value, got 'count'"),
+            ("wave 1\ngroup shooter count=lots", "line 2: bad number for count: 'lots'"),
+            ("wave 1\ngroup shooter formation=spiral", "line 2: unknown formation 'spiral'"),
+            ("wave 1\ngroup shooter trigger=soon", "line 2: bad trigger 'soon'"),
+            ("wave 1\ngroup shooter trigger=time:x", "line 2: bad trigger 'time:x'"),
+            ("wave 1\ngroup shooter trigger=hp:150", "line 2: hp trigger must be 0-100, got 150"),
+            ("wave 1\ngroup shooter count=0", "line 2: count must be positive and delay not negative"),
+            ("wave 1\ngroup shooter delay=-1", "line 2: count must be positive and delay not negative"),
+        ];
+        for (data, err) in bad {
+            assert_eq!(parse_waves(data, &NAMES).err(), Some(format!("waves.txt {}", err)), "{}", data);
+        }
+
+        assert_eq!(parse_waves("wave 1\ngroup shooter\nwave 2", &NAMES).err(), Some(String::from("waves.txt: wave 2 has no groups")));
+    }
+
+    #[test]
+    fn time_trigger_waits() {
+        let w = wave(vec![group(Trigger::Time(0.0), 0.0), group(Trigger::Time(1.0), 0.0)]);
+        let mut r = ScriptRunner::new();
+
+        assert_eq!(r.update(&w, DT, 0, 0.0), Some(0));
+        // 1 second at 0.1 a step, a little slack for float error
+        let steps = steps_until_spawn(&mut r, &w, 5, 10.0, 20).unwrap();
+        assert!((10..=11).contains(&steps), "{}", steps);
+        assert_eq!(r.next_group, 2);
+        assert_eq!(r.update(&w, DT, 0, 0.0), None);
+    }
+
+    #[test]
+    fn time_trigger_fires_early_when_empty() {
+        let w = wave(vec![group(Trigger::Time(0.0), 0.0), group(Trigger::Time(30.0), 0.0)]);
+        let mut r = ScriptRunner::new();
+
+        assert_eq!(r.update(&w, DT, 0, 0.0), Some(0));
+        assert_eq!(r.update(&w, DT, 3, 10.0), None);
+        assert_eq!(r.update(&w, DT, 0, 0.0), Some(1));
+    }
+
+    #[test]
+    fn cleared_trigger() {
+        let w = wave(vec![group(Trigger::Time(0.0), 0.0), group(Trigger::Cleared, 0.0)]);
+        let mut r = ScriptRunner::new();
+
+        assert_eq!(r.update(&w, DT, 0, 0.0), Some(0));
+        assert_eq!(steps_until_spawn(&mut r, &w, 1, 2.0, 100), None);
+        assert_eq!(r.update(&w, DT, 0, 0.0), Some(1));
+    }
+
+    #[test]
+    fn health_trigger() {
+        let w = wave(vec![group(Trigger::Time(0.0), 0.0), group(Trigger::Health(0.5), 0.0)]);
+        let mut r = ScriptRunner::new();
+
+        assert_eq!(r.update(&w, DT, 0, 0.0), Some(0));
+        r.set_group_health(20.0);
+        assert_eq!(r.update(&w, DT, 4, 20.0), None);
+        assert_eq!(r.update(&w, DT, 3, 10.1), None);
+        assert_eq!(r.update(&w, DT, 2, 10.0), Some(1));
+    }
+
+    #[test]
+    fn health_trigger_measures_from_the_last_group() {
+        let w = wave(vec![
+            group(Trigger::Time(0.0), 0.0),
+            group(Trigger::Time(0.0), 0.0),
+            group(Trigger::Health(0.5), 0.0),
+        ]);
+        let mut r = ScriptRunner::new();
+
+        // 20 health in, half of it killed, then another 20 on top
+        assert_eq!(r.update(&w, DT, 0, 0.0), Some(0));
+        r.set_group_health(20.0);
+        assert_eq!(r.update(&w, DT, 2, 10.0), Some(1));
+        r.set_group_health(10.0 + 20.0);
+
+        // half of the 30 on screen, not half of the 40 spawned all wave
+        assert_eq!(r.update(&w, DT, 3, 20.0), None);
+        assert_eq!(r.update(&w, DT, 2, 15.0), Some(2));
+    }
+
+    #[test]
+    fn delay_after_trigger() {
+        let w = wave(vec![group(Trigger::Cleared, 0.5)]);
+        let mut r = ScriptRunner::new();
+
+        // the trigger sticks even if enemies show up during the delay
+        assert_eq!(r.update(&w, DT, 0, 0.0), None);
+        let steps = steps_until_spawn(&mut r, &w, 2, 4.0, 20).unwrap();
+        assert!((4..=5).contains(&steps), "{}", steps);
+    }
+
+    #[test]
+    fn archetypes_match_enemy_list() {
+        let enemies = default_enemies();
+        for (name, i, kind) in ARCHETYPES {
+            assert!(enemies[i].kind == kind, "{} is at the wrong enemy_list index", name);
+        }
+
+        let find = |name: &str| &enemies[ARCHETYPES.iter().find(|(n, _, _)| *n == name).unwrap().1];
+        assert!(find("swarm_primary").state == ColorState::Primary);
+        assert!(find("swarm_secondary").state == ColorState::Secondary);
+        assert!(find("flicker").flip == FlipMode::Timer);
+        assert!(find("mimic").flip == FlipMode::OnSwitch);
+        assert!(find("swarm_primary").flip == FlipMode::Never);
+    }
+}
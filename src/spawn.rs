@@ -0,0 +1,150 @@
+use macroquad::prelude::*;
+use crate::game::*;
+use crate::enemy::*;
+use crate::colors::*;
+use crate::daily::RunRng;
+
+// closest an enemy can appear to the player
+pub const SPAWN_MIN_PLAYER_DIST: f32 = 250.0;
+pub const SPAWN_TIME: f32 = 2.0;
+const SPAWN_MARGIN: f32 = 50.0;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Formation {
+    Ring,    // circle around the player
+    Line,    // a row off to one side
+    Edge,    // lined up along one edge of the screen
+    Cluster, // loose group somewhere away from the player
+}
+
+fn clamp_to_arena(p: Vec2) -> Vec2 {
+    Vec2 {
+        x: p.x.clamp(SPAWN_MARGIN, DESIGN_WIDTH - SPAWN_MARGIN),
+        y: p.y.clamp(SPAWN_MARGIN, DESIGN_HEIGHT - SPAWN_MARGIN),
+    }
+}
+
+// push a point out of the safe zone around the player, and keep it on screen
+pub fn safe_spawn_point(p: Vec2, player: Vec2) -> Vec2 {
+    let mut pos = clamp_to_arena(p);
+    if pos.distance(player) >= SPAWN_MIN_PLAYER_DIST {
+        return pos;
+    }
+
+    let mut away = (pos - player).normalize_or_zero();
+    if away == Vec2::ZERO {
+        away = Vec2::X;
+    }
+    pos = clamp_to_arena(player + away * SPAWN_MIN_PLAYER_DIST);
+
+    // pinned against a wall, go to the other side of the player instead
+    if pos.distance(player) < SPAWN_MIN_PLAYER_DIST {
+        pos = clamp_to_arena(player - away * SPAWN_MIN_PLAYER_DIST);
+    }
+    pos
+}
+
+// a point far enough from the player to build a formation around
+fn random_anchor(player: Vec2, rng: &RunRng) -> Vec2 {
+    for _ in 0..10 {
+        let p = Vec2 {
+            x: rng.range(SPAWN_MARGIN, DESIGN_WIDTH - SPAWN_MARGIN),
+            y: rng.range(SPAWN_MARGIN, DESIGN_HEIGHT - SPAWN_MARGIN),
+        };
+        if p.distance(player) > SPAWN_MIN_PLAYER_DIST * 1.5 {
+            return p;
+        }
+    }
+    safe_spawn_point(player, player)
+}
+
+// centers for count enemies in the given formation
+pub fn formation_positions(f: Formation, count: i32, player: Vec2, rng: &RunRng) -> Vec<Vec2> {
+    let mut out = Vec::new();
+    match f {
+        Formation::Ring => {
+            let radius = SPAWN_MIN_PLAYER_DIST + 100.0;
+            let start = rng.range(0.0, 360.0);
+            for i in 0..count {
+                let angle = start + 360.0 / count as f32 * i as f32;
+                out.push(player + rotate_vec(Vec2::X, angle) * radius);
+            }
+        }
+        Formation::Line => {
+            let anchor = random_anchor(player, rng);
+            let side = (anchor - player).normalize_or_zero().perp();
+            let spacing = 60.0;
+            for i in 0..count {
+                let offset = (i as f32 - (count - 1) as f32 / 2.0) * spacing;
+                out.push(anchor + side * offset);
+            }
+        }
+        Formation::Edge => {
+            // pick the edge furthest from the player
+            let edges = [
+                (player.y, Vec2 { x: 0.0, y: SPAWN_MARGIN }, Vec2::X),
+                (DESIGN_HEIGHT - player.y, Vec2 { x: 0.0, y: DESIGN_HEIGHT - SPAWN_MARGIN }, Vec2::X),
+                (player.x, Vec2 { x: SPAWN_MARGIN, y: 0.0 }, Vec2::Y),
+                (DESIGN_WIDTH - player.x, Vec2 { x: DESIGN_WIDTH - SPAWN_MARGIN, y: 0.0 }, Vec2::Y),
+            ];
+            let (_, start, along) = edges.iter().copied()
+                .max_by(|a, b| a.0.total_cmp(&b.0))
+                .unwrap();
+            let length = if along == Vec2::X { DESIGN_WIDTH } else { DESIGN_HEIGHT };
+            for i in 0..count {
+                out.push(start + along * (length / (count + 1) as f32 * (i + 1) as f32));
+            }
+        }
+        Formation::Cluster => {
+            let anchor = random_anchor(player, rng);
+            for _ in 0..count {
+                let offset = rotate_vec(Vec2::X, rng.range(0.0, 360.0)) * rng.range(0.0, 150.0);
+                out.push(anchor + offset);
+            }
+        }
+    }
+
+    out.into_iter().map(|p| safe_spawn_point(p, player)).collect()
+}
+
+impl Game {
+    pub fn spawn_group(&mut self, enemies: Vec<Enemy>, formation: Formation) {
+        let player = Vec2 { x: self.player.x + self.player.size / 2.0, y: self.player.y + self.player.size / 2.0 };
+        let positions = formation_positions(formation, enemies.len() as i32, player, &self.run_rng);
+
+        for (e, pos) in enemies.into_iter().zip(positions) {
+            let e = self.mutate_enemy(e);
+            self.enemy_spawn.push(
+                SpawnEnemy {
+                    x: pos.x - e.size / 2.0,
+                    y: pos.y - e.size / 2.0,
+                    spawn_t: SPAWN_TIME,
+                    to_spawn: e,
+                }
+            );
+        }
+    }
+
+    pub fn draw_spawning(&mut self,s: &mut SpawnEnemy) {
+        // show what's coming and in which color, so the switch can be planned
+        let e = s.to_spawn;
+        let mut color = match e.state {
+            ColorState::Primary => self.palette.fg_primary,
+            ColorState::Secondary => self.palette.fg_secondary
+        };
+        let progress = (SPAWN_TIME - s.spawn_t) / SPAWN_TIME;
+
+        color.a = 0.8;
+        draw_rectangle_lines(s.x, s.y, e.size, e.size, 4.0, color);
+
+        color.a = 0.2 + 0.6 * progress;
+        let curr_size = e.size * progress;
+        let x = s.x + e.size/2.0 - curr_size/2.0;
+        let y = s.y + e.size/2.0 - curr_size/2.0;
+        match self.enemy_sprite(e.kind) {
+            Some(texture) => draw_texture_ex(texture, x, y, color,
+                DrawTextureParams { dest_size: Some(Vec2 { x: curr_size, y: curr_size }), ..Default::default() }),
+            None => draw_rectangle(x, y, curr_size, curr_size, color),
+        }
+    }
+}
@@ -0,0 +1,235 @@
+use std::collections::HashMap;
+
+use macroquad::prelude::*;
+use crate::enemy::*;
+use crate::game::*;
+
+// How much each behaviour pulls on an enemy, picked per archetype
+#[derive(Clone, Copy)]
+pub struct SteeringWeights {
+    pub seek: f32,
+    pub separation: f32,
+    pub arrival: f32,
+    pub orbit: f32,
+    pub flee: f32,
+    pub preferred_range: f32, // arrival tries to stay this far from the player
+    pub flee_radius: f32,     // flee only kicks in when the player is closer than this
+    pub separation_radius: f32,
+}
+
+impl SteeringWeights {
+    pub fn default() -> Self {
+        SteeringWeights {
+            seek: 0.0,
+            separation: 0.0,
+            arrival: 0.0,
+            orbit: 0.0,
+            flee: 0.0,
+            preferred_range: 0.0,
+            flee_radius: 0.0,
+            separation_radius: 50.0,
+        }
+    }
+}
+
+impl EnemyType {
+    pub fn steering(&self) -> SteeringWeights {
+        match self {
+            // swarm straight in, but don't collapse into one blob
+            EnemyType::FollowEnemy => SteeringWeights {
+                seek: 1.0,
+                separation: 1.4,
+                separation_radius: 35.0,
+                ..SteeringWeights::default()
+            },
+            // close the gap, then circle at range and back off if rushed
+            EnemyType::FollowShootEnemy => SteeringWeights {
+                separation: 1.2,
+                arrival: 1.0,
+                orbit: 0.4,
+                flee: 1.5,
+                preferred_range: 250.0,
+                flee_radius: 120.0,
+                separation_radius: 70.0,
+                ..SteeringWeights::default()
+            },
+            EnemyType::PatternShooter => SteeringWeights {
+                separation: 1.5,
+                arrival: 1.0,
+                orbit: 0.2,
+                preferred_range: 400.0,
+                separation_radius: 120.0,
+                ..SteeringWeights::default()
+            },
+            EnemyType::Charger | EnemyType::Splitter | EnemyType::Shielded => SteeringWeights {
+                seek: 1.0,
+                separation: 1.2,
+                separation_radius: 60.0,
+                ..SteeringWeights::default()
+            },
+            // hangs around at mid range, it gets close by blinking
+            EnemyType::Teleporter => SteeringWeights {
+                separation: 1.0,
+                arrival: 1.0,
+                orbit: 0.6,
+                preferred_range: 300.0,
+                separation_radius: 80.0,
+                ..SteeringWeights::default()
+            },
+            // stays as far back as it can, lines up shots from there
+            EnemyType::Sniper => SteeringWeights {
+                separation: 1.0,
+                arrival: 1.0,
+                flee: 1.5,
+                preferred_range: 550.0,
+                flee_radius: 250.0,
+                separation_radius: 100.0,
+                ..SteeringWeights::default()
+            },
+            EnemyType::StaticCircleAttack => SteeringWeights::default(),
+        }
+    }
+}
+
+// Buckets enemy ids and centers into a grid so neighbour lookups don't check every pair
+pub struct SpatialGrid {
+    pub cell_size: f32,
+    pub cells: HashMap<(i32, i32), Vec<(u32, Vec2)>>,
+}
+
+impl SpatialGrid {
+    pub fn new(cell_size: f32) -> Self {
+        SpatialGrid { cell_size, cells: HashMap::new() }
+    }
+
+    fn cell(&self, p: Vec2) -> (i32, i32) {
+        ((p.x / self.cell_size).floor() as i32, (p.y / self.cell_size).floor() as i32)
+    }
+
+    pub fn build(&mut self, enemies: &[Enemy]) {
+        self.cells.clear();
+        for e in enemies {
+            let center = e.center();
+            let cell = self.cell(center);
+            self.cells.entry(cell).or_default().push((e.id, center));
+        }
+    }
+
+    // every center within radius of p, p itself included if it's in the grid
+    pub fn neighbours(&self, p: Vec2, radius: f32) -> Vec<(u32, Vec2)> {
+        let (cx, cy) = self.cell(p);
+        let reach = (radius / self.cell_size).ceil() as i32;
+        let mut out = Vec::new();
+
+        for x in cx - reach..=cx + reach {
+            for y in cy - reach..=cy + reach {
+                if let Some(cell) = self.cells.get(&(x, y)) {
+                    out.extend(cell.iter().filter(|(_, n)| n.distance(p) < radius));
+                }
+            }
+        }
+        out
+    }
+}
+
+pub fn seek(pos: Vec2, target: Vec2) -> Vec2 {
+    (target - pos).normalize_or_zero()
+}
+
+pub fn flee(pos: Vec2, target: Vec2) -> Vec2 {
+    -seek(pos, target)
+}
+
+// towards the target until preferred range, away when inside it, slowing near the edge
+pub fn arrival(pos: Vec2, target: Vec2, range: f32) -> Vec2 {
+    let slow_radius = 100.0;
+    let dist = pos.distance(target);
+    seek(pos, target) * ((dist - range) / slow_radius).clamp(-1.0, 1.0)
+}
+
+pub fn orbit(pos: Vec2, target: Vec2) -> Vec2 {
+    seek(pos, target).perp()
+}
+
+pub fn separation(id: u32, pos: Vec2, neighbours: &[(u32, Vec2)], radius: f32) -> Vec2 {
+    let mut push = Vec2::ZERO;
+    for (other, n) in neighbours {
+        if *other == id {
+            continue;
+        }
+        let away = pos - *n;
+        let dist = away.length();
+        if dist > 0.0 {
+            // closer neighbours push harder
+            push += away / dist * (1.0 - dist / radius);
+        } else {
+            // stacked on the same point, like splitter children, so there's no away to go.
+            // pick one off the pair's ids, the lower id goes one way and the higher the other
+            let dir = rotate_vec(Vec2::X, id.min(*other) as f32 * 137.5);
+            push += if id < *other { dir } else { -dir };
+        }
+    }
+    push
+}
+
+impl Game {
+    // direction this enemy wants to move in, length 1 at most
+    pub fn steer(&self, e: &Enemy) -> Vec2 {
+        let w = e.kind.steering();
+        let pos = e.center();
+        let target = Vec2 { x: self.player.x + self.player.size / 2.0, y: self.player.y + self.player.size / 2.0 };
+
+        let mut force = Vec2::ZERO;
+        if w.seek > 0.0 {
+            force += seek(pos, target) * w.seek;
+        }
+        if w.arrival > 0.0 {
+            force += arrival(pos, target, w.preferred_range) * w.arrival;
+        }
+        if w.orbit > 0.0 {
+            // alternate orbit direction per enemy so they don't all circle the same way
+            let side = if e.id.is_multiple_of(2) { 1.0 } else { -1.0 };
+            force += orbit(pos, target) * w.orbit * side;
+        }
+        if w.flee > 0.0 && pos.distance(target) < w.flee_radius {
+            force += flee(pos, target) * w.flee;
+        }
+        if w.separation > 0.0 {
+            let neighbours = self.enemy_grid.neighbours(pos, w.separation_radius);
+            force += separation(e.id, pos, &neighbours, w.separation_radius) * w.separation;
+        }
+
+        force.clamp_length_max(1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn separation_skips_itself() {
+        let pos = vec2(100.0, 100.0);
+        assert_eq!(separation(1, pos, &[(1, pos)], 50.0), Vec2::ZERO);
+    }
+
+    #[test]
+    fn separation_pushes_away() {
+        let push = separation(1, vec2(100.0, 100.0), &[(1, vec2(100.0, 100.0)), (2, vec2(90.0, 100.0))], 50.0);
+        assert!(push.x > 0.0 && push.y.abs() < 1e-5);
+        // a closer neighbour pushes harder
+        let closer = separation(1, vec2(100.0, 100.0), &[(2, vec2(95.0, 100.0))], 50.0);
+        assert!(closer.length() > push.length());
+    }
+
+    #[test]
+    fn separation_splits_stacked_enemies() {
+        let pos = vec2(100.0, 100.0);
+        let a = separation(3, pos, &[(3, pos), (7, pos)], 50.0);
+        let b = separation(7, pos, &[(3, pos), (7, pos)], 50.0);
+        assert!(a.length() > 0.9);
+        assert!((a + b).length() < 1e-5);
+        // and the same every time
+        assert_eq!(a, separation(3, pos, &[(7, pos)], 50.0));
+    }
+}
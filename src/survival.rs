@@ -0,0 +1,135 @@
+use macroquad::prelude::*;
+use crate::game::*;
+use crate::wave::*;
+use crate::script::*;
+use crate::rules::*;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum GameMode {
+    Classic,
+    Survival,
+    Daily,      // classic waves with a fixed seed and setup for the day
+}
+
+// Difficulty goes up a level this often, a level buys enemies like a wave does
+pub const SURVIVAL_LEVEL_TIME: f32 = 30.0;
+pub const SURVIVAL_BASE_MAX_ALIVE: usize = 25;
+pub const SURVIVAL_LEADERBOARD_SIZE: usize = 5;
+
+pub struct Survival {
+    pub time: f32,
+    pub next_upgrade_score: i32,
+    pub upgrades_taken: i32,
+}
+
+impl Survival {
+    pub fn default() -> Self {
+        Survival { time: 0.0, next_upgrade_score: 200, upgrades_taken: 0 }
+    }
+
+    pub fn level(&self) -> i32 {
+        1 + (self.time / SURVIVAL_LEVEL_TIME) as i32
+    }
+
+    // thresholds spread out so upgrades don't come faster than score does
+    pub fn advance_upgrade_score(&mut self) {
+        self.upgrades_taken += 1;
+        self.next_upgrade_score += 200 + 150 * self.upgrades_taken;
+    }
+}
+
+pub fn format_time(seconds: f32) -> String {
+    let s = seconds as i32;
+    format!("{:02}:{:02}", s / 60, s % 60)
+}
+
+pub struct SurvivalRecord {
+    pub time: f32,
+    pub mutators: Vec<Mutator>,
+}
+
+// Saved as comma separated seconds|mutators, best first
+pub fn parse_leaderboard(s: &str) -> Vec<SurvivalRecord> {
+    s.split(',')
+        .filter_map(|entry| {
+            let (time, mutators) = entry.split_once('|').unwrap_or((entry, ""));
+            Some(SurvivalRecord { time: time.parse().ok()?, mutators: parse_mutators(mutators) })
+        })
+        .collect()
+}
+
+pub fn leaderboard_to_string(times: &[SurvivalRecord]) -> String {
+    times.iter()
+        .map(|r| format!("{:.1}|{}", r.time, mutators_to_string(&r.mutators)))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+impl Game {
+    pub fn survival_update(&mut self) {
+        match self.wave.state {
+            WaveState::Start => {
+                if !self.wave.upgrades_spawned {
+                    self.spawn_upgrades();
+                }
+            }
+            WaveState::Spawning => {
+                if self.switch_effect_t > 0.0 {
+                    return;
+                }
+
+                self.survival.time += get_frame_time();
+                self.wave.current = self.survival.level();
+
+                // let the screen clear out, then open the upgrade room
+                if self.current_score >= self.survival.next_upgrade_score {
+                    if self.enemies.is_empty() && self.enemy_spawn.is_empty() {
+                        self.survival.advance_upgrade_score();
+                        self.wave.state = WaveState::Start;
+                        self.wave.upgrade_picked = false;
+                    }
+                    return;
+                }
+
+                let finished = self.generated_wave.as_ref()
+                    .is_none_or(|w| self.wave_runner.next_group >= w.groups.len());
+                if finished {
+                    self.generate_next_wave();
+                    self.wave_runner = ScriptRunner::new();
+                    self.wave.enemy_remaining = self.scripted_wave().map_or(0, |w| w.enemy_count());
+                }
+
+                let max_alive = SURVIVAL_BASE_MAX_ALIVE + self.wave.current as usize * 2;
+                if self.enemies.len() + self.enemy_spawn.len() < max_alive {
+                    self.update_wave_script();
+                }
+            }
+        }
+    }
+
+    // true if it's the new longest run
+    pub fn record_survival_time(&mut self) -> bool {
+        let time = self.survival.time;
+        let best = self.survival_times.first().is_none_or(|r| time > r.time);
+
+        self.survival_times.push(SurvivalRecord { time, mutators: self.rules.mutators.clone() });
+        self.survival_times.sort_by(|a, b| b.time.total_cmp(&a.time));
+        self.survival_times.truncate(SURVIVAL_LEADERBOARD_SIZE);
+        self.should_save = true;
+        best
+    }
+
+    pub fn survival_leaderboard_draw(&self, x: f32, y: f32) {
+        draw_text_centered("Longest survived", x, y, 10.0, &self.assets.font_monogram);
+        if self.survival_times.is_empty() {
+            draw_text_centered("-", x, y + 50.0, 8.0, &self.assets.font_monogram);
+        }
+        for (i, r) in self.survival_times.iter().enumerate() {
+            let y = y + 50.0 + i as f32 * 50.0;
+            draw_text_centered(&format!("{}. {}", i + 1, format_time(r.time)), x, y, 8.0, &self.assets.font_monogram);
+            if !r.mutators.is_empty() {
+                draw_text_centered_c(&mutators_to_string(&r.mutators), x, y + 20.0, 5.0, &self.assets.font_monogram, GRAY);
+            }
+        }
+    }
+}
@@ -1,6 +1,6 @@
 
 use macroquad::prelude::*;
-use crate::{assets::Assets, colors::ColorState, game::*, wave::WaveState};
+use crate::{assets::Assets, collision::Shape, colors::ColorState, game::*, wave::WaveState};
 
 
 pub struct Upgrade {
@@ -16,7 +16,15 @@ pub struct Upgrade {
 pub enum UpgradeKind {
     Speed,
     Projectile,
-    SlowDamage
+    SlowDamage,
+    Pierce,
+    Bounce,
+    Homing,
+    Split,
+    Accelerate,
+    Laser,
+    Cooldown,
+    AbilityCharge,
 }
 
 impl UpgradeKind {
@@ -25,6 +33,14 @@ impl UpgradeKind {
             Self::Speed => &asset.speed,
             Self::Projectile => &asset.projectile,
             Self::SlowDamage => &asset.slowdmg,
+            Self::Pierce => &asset.projectile,
+            Self::Bounce => &asset.bounce,
+            Self::Homing => &asset.homing,
+            Self::Split => &asset.split,
+            Self::Accelerate => &asset.slowdmg,
+            Self::Laser => &asset.projectile,
+            Self::Cooldown => &asset.speed,
+            Self::AbilityCharge => &asset.maxhp,
         }
     }
 }
@@ -37,6 +53,26 @@ pub enum UpgradeRarity {
     Legendary = 1,
 }
 
+impl UpgradeRarity {
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Common => "Common",
+            Self::Rare => "Rare",
+            Self::Epic => "Epic",
+            Self::Legendary => "Legendary",
+        }
+    }
+
+    pub fn get_frame<'a>(&'a self, asset: &'a Assets) -> &Texture2D {
+        match self {
+            Self::Common => &asset.upgrade_frame,
+            Self::Rare => &asset.upgrade_frame2,
+            Self::Epic => &asset.upgrade_frame3,
+            Self::Legendary => &asset.upgrade_frame4,
+        }
+    }
+}
+
 
 pub struct UpgradeEntity {
     pub x: f32,
@@ -54,25 +90,81 @@ impl UpgradeEntity {
 }
 
 impl Game {
+    pub fn apply_upgrade(&mut self, idx: usize) {
+        self.upgrade_picks[idx] += 1;
+        self.should_save = true;
+
+        let p = &mut self.player;
+        match self.upg_list[idx].kind {
+            UpgradeKind::Speed => {
+                p.move_speed += 30.0;
+            }
+            UpgradeKind::Projectile => {
+                p.projectiles += 1.0;
+                p.damage = f32::max(0.5, p.damage - 1.0);
+                p.spread += 3.0;
+            }
+            UpgradeKind::SlowDamage => {
+                p.damage += 1.0;
+                p.bullet_speed -= 30.0;
+            }
+            UpgradeKind::Pierce => {
+                p.bullet_mods.pierce += 1;
+            }
+            UpgradeKind::Bounce => {
+                p.bullet_mods.bounces += 2;
+            }
+            UpgradeKind::Homing => {
+                p.bullet_mods.homing += 120.0;
+            }
+            UpgradeKind::Split => {
+                p.bullet_mods.split += 2;
+            }
+            UpgradeKind::Cooldown => {
+                p.ability.cooldown *= 0.75;
+            }
+            UpgradeKind::AbilityCharge => {
+                p.ability.max_charges += 1;
+                p.ability.charges += 1;
+                p.ability.power += 0.25;
+            }
+            UpgradeKind::Laser => {
+                p.laser_level += 1;
+            }
+            UpgradeKind::Accelerate => {
+                p.bullet_mods.acceleration += 600.0;
+                p.bullet_mods.lifetime = if p.bullet_mods.lifetime > 0.0 { p.bullet_mods.lifetime * 0.8 } else { 1.2 };
+            }
+        }
+    }
+
     pub fn update_upgrades(&mut self) {
         self.upgrade_shown = 1000;
+        let mut picked = None;
         for upg in &self.upgrades {
             //let upg = &self.upg_list[upg.upg_index];
             let rect = upg.get_rect();
 
-            if rect_collide(rect, self.player.get_rect()) {
+            if self.player.get_shape().overlaps(&Shape::Aabb(rect)) {
                 self.upgrade_shown = upg.upg_index;
 
                 // Chose this upgrade, do the funi
                 if is_key_pressed(KeyCode::Space) {
-
+                    picked = Some(upg.upg_index);
                 }
             }
         }
 
+        if let Some(idx) = picked {
+            self.apply_upgrade(idx);
+            self.wave.state = WaveState::Spawning;
+            self.wave.upgrades_spawned = false;
+            self.wave.upgrade_picked = true;
+        }
+
         // draw_rectangle(50.0, 415.0, 300.0, 70.0, WHITE);  // Reroll
         let reroll_rect = Rect { x: 50.0, y: 415.0, w: 300.0, h: 70.0};
-        if rect_collide(self.player.get_rect(), reroll_rect) {
+        if self.player.get_shape().overlaps(&Shape::Aabb(reroll_rect)) {
             self.upgrade_shown = 1001;
             if self.player.reroll > 0 && is_key_pressed(KeyCode::Space) {
                 self.upgrades = Vec::new();
@@ -83,7 +175,7 @@ impl Game {
 
         // draw_rectangle(50.0, 565.0, 450.0, 70.0, WHITE);  // Skip
         let skip_rect = Rect { x: 50.0, y: 565.0, w: 450.0, h: 70.0};
-        if rect_collide(self.player.get_rect(), skip_rect) {
+        if self.player.get_shape().overlaps(&Shape::Aabb(skip_rect)) {
             self.upgrade_shown = 1002;
             if is_key_pressed(KeyCode::Space) {
                 self.wave.state = WaveState::Spawning;
@@ -216,7 +308,7 @@ impl Game {
         let mut picked_upgrades_idx = Vec::new();
 
         for _ in 0..self.upgrade_count as i32 {
-            let random = macroquad::rand::gen_range(1, sum);
+            let random = 1 + self.run_rng.below((sum - 1) as usize) as i32;
 
             let mut local_sum = self.upg_list[ all_upgrades_idx[0] ].rarity as i32;
             let mut idx = 1;
@@ -232,6 +324,11 @@ impl Game {
             all_upgrades_idx.remove(idx);
         }
         
+        for idx in picked_upgrades_idx.iter() {
+            self.upgrade_seen[*idx] = true;
+        }
+        self.should_save = true;
+
         for i in 0..self.upgrade_count as i32 {
             let center_x = DESIGN_WIDTH/2.0;
             let x = center_x - start + i as f32*(upg_size + padding);
@@ -19,9 +19,6 @@ pub struct Wave {
     pub old_x: f32,
     pub old_y: f32,
 
-    pub spawn_delay_t: f32,
-    pub spawn_delay_tmax: f32,
-
     pub start_spawned: bool,
     pub upgrades_spawned: bool,
 }
@@ -34,8 +31,6 @@ impl Wave {
             enemy_remaining: 0, 
             enemies_set: false,
             start_spawned: false, 
-            spawn_delay_tmax: 5.0,
-            spawn_delay_t: 0.0,
             upgrades_spawned: false, 
             upgrade_picked: false,
 